@@ -119,12 +119,14 @@ impl kernel::mpu::MPU for MPU {
                region_num: u32,
                start_addr: u32,
                len: u32,
+               subregions: u8,
                execute: kernel::mpu::ExecutePermission,
                access: kernel::mpu::AccessPermission) {
         let regs = unsafe { &*self.0 };
         regs.region_base_address.set(region_num | 1 << 4 | start_addr);
         let xn = execute as u32;
         let ap = access as u32;
-        regs.region_attributes_and_size.set(1 | len << 1 | ap << 24 | xn << 28);
+        let srd = subregions as u32;
+        regs.region_attributes_and_size.set(1 | len << 1 | srd << 8 | ap << 24 | xn << 28);
     }
 }
@@ -37,3 +37,15 @@ pub unsafe fn reset() {
     let reset = (0x5FA << 16) | (aircr & (0x7 << 8)) | (1 << 2);
     (*SCB).aircr.set(reset);
 }
+
+/// Makes `wfi` enter the chip's deep sleep modes rather than normal sleep.
+pub unsafe fn set_sleepdeep() {
+    let scr = (*SCB).scr.get();
+    (*SCB).scr.set(scr | 1 << 2);
+}
+
+/// Makes `wfi` enter normal sleep.
+pub unsafe fn unset_sleepdeep() {
+    let scr = (*SCB).scr.get();
+    (*SCB).scr.set(scr & !(1 << 2));
+}
@@ -37,14 +37,23 @@ unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'sta
 
     const NUM_PROCS: usize = 2;
 
-    // how should the kernel respond when a process faults
-    const FAULT_RESPONSE: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    // how should the kernel respond when each process faults
+    const PANIC: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    const FAULT_RESPONSES: [kernel::process::FaultResponse; NUM_PROCS] = [PANIC, PANIC];
+
+    // Public key that apps must be signed with, as the big-endian X and Y
+    // coordinates of a P-256 point. With `None`, unsigned apps are loaded too.
+    const APP_SIGNING_KEY: Option<&'static [u8; 64]> = None;
 
     #[link_section = ".app_memory"]
     static mut APP_MEMORY: [u8; 16384] = [0; 16384];
 
     static mut PROCESSES: [Option<kernel::process::Process<'static>>; NUM_PROCS] = [None, None];
 
+    if let Some(key) = APP_SIGNING_KEY {
+        kernel::process::set_app_signing_key(key);
+    }
+
     let mut apps_in_flash_ptr = &_sapps as *const u8;
     let mut app_memory_ptr = APP_MEMORY.as_mut_ptr();
     let mut app_memory_size = APP_MEMORY.len();
@@ -53,13 +62,14 @@ unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'sta
             kernel::process::Process::create(apps_in_flash_ptr,
                                              app_memory_ptr,
                                              app_memory_size,
-                                             FAULT_RESPONSE);
+                                             FAULT_RESPONSES[i]);
 
-        if process.is_none() {
-            break;
+        match process {
+            Ok(process) => PROCESSES[i] = Some(process),
+            Err(kernel::process::LoadError::NoValidHeader) => break,
+            Err(err) => debug!("App {} failed to load: {:?}", i, err),
         }
 
-        PROCESSES[i] = process;
         apps_in_flash_ptr = apps_in_flash_ptr.offset(flash_offset as isize);
         app_memory_ptr = app_memory_ptr.offset(memory_offset as isize);
         app_memory_size -= memory_offset;
@@ -186,8 +196,9 @@ pub unsafe fn reset_handler() {
                      115200,
                      &mut console::WRITE_BUF,
                      kernel::Container::create()),
-        224/8);
+        256/8);
     hil::uart::UART::set_client(&usart::USART0, console);
+    kernel::container::add_reset_client(console);
 
     // Create the Nrf51822Serialization driver for passing BLE commands
     // over UART to the nRF51822 radio.
@@ -198,6 +209,7 @@ pub unsafe fn reset_handler() {
                                    &mut nrf51822_serialization::READ_BUF),
         608/8);
     hil::uart::UART::set_client(&usart::USART3, nrf_serialization);
+    kernel::container::add_reset_client(nrf_serialization);
 
     let ast = &sam4l::ast::AST;
 
@@ -227,6 +239,7 @@ pub unsafe fn reset_handler() {
         352/8);
     si7021_i2c.set_client(si7021);
     si7021_virtual_alarm.set_client(si7021);
+    kernel::container::add_reset_client(si7021);
 
     // Configure the ISL29035, device address 0x44
     let isl29035_i2c = static_init!(I2CDevice, I2CDevice::new(sensors_i2c, 0x44), 32);
@@ -241,6 +254,7 @@ pub unsafe fn reset_handler() {
         384/8);
     isl29035_i2c.set_client(isl29035);
     isl29035_virtual_alarm.set_client(isl29035);
+    kernel::container::add_reset_client(isl29035);
 
     // Timer
     let virtual_alarm1 = static_init!(
@@ -252,6 +266,7 @@ pub unsafe fn reset_handler() {
         TimerDriver::new(virtual_alarm1, kernel::Container::create()),
         12);
     virtual_alarm1.set_client(timer);
+    kernel::container::add_reset_client(timer);
 
     // FXOS8700CQ accelerometer, device address 0x
     let fxos8700_i2c = static_init!(I2CDevice, I2CDevice::new(sensors_i2c, 0x1e), 32);
@@ -260,6 +275,7 @@ pub unsafe fn reset_handler() {
         capsules::fxos8700_cq::Fxos8700cq::new(fxos8700_i2c, &mut capsules::fxos8700_cq::BUF),
         352/8);
     fxos8700_i2c.set_client(fxos8700);
+    kernel::container::add_reset_client(fxos8700);
 
     // Initialize and enable SPI HAL
     // Set up an SPI MUX, so there can be multiple clients
@@ -287,6 +303,7 @@ pub unsafe fn reset_handler() {
 
     spi_syscalls.config_buffers(&mut SPI_READ_BUF, &mut SPI_WRITE_BUF);
     syscall_spi_device.set_client(spi_syscalls);
+    kernel::container::add_reset_client(spi_syscalls);
 
     // LEDs
     let led_pins = static_init!(
@@ -319,6 +336,7 @@ pub unsafe fn reset_handler() {
         capsules::adc::ADC::new(&mut sam4l::adc::ADC),
         224/8);
     sam4l::adc::ADC.set_client(adc);
+    kernel::container::add_reset_client(adc);
 
     // Setup RNG
     let rng = static_init!(
@@ -344,6 +362,7 @@ pub unsafe fn reset_handler() {
     for pin in gpio_pins.iter() {
         pin.set_client(gpio);
     }
+    kernel::container::add_reset_client(gpio);
 
 
 
@@ -385,6 +404,10 @@ pub unsafe fn reset_handler() {
     // Uncomment to measure overheads for TakeCell and MapCell:
     // test_take_map_cell::test_take_map_cell();
 
+    // Run processes in turn, 10ms each
+    let timeslices = [kernel::scheduler::DEFAULT_TIMESLICE_US; 2];
+    let scheduler = kernel::scheduler::RoundRobin::new(&timeslices);
+
     debug!("Initialization complete. Entering main loop");
-    kernel::main(&hail, &mut chip, load_processes(), &hail.ipc);
+    kernel::main(&hail, &mut chip, load_processes(), &hail.ipc, &scheduler);
 }
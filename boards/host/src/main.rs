@@ -0,0 +1,154 @@
+//! Board file for running the kernel as a Linux process.
+//!
+//! The console is attached to stdin and stdout, LEDs are logged to stderr,
+//! and the SPI and I2C buses are simulated, so capsules can be tried out and
+//! debugged without hardware. Pass `--self-test` to exercise the virtualized
+//! alarm, SPI, I2C, GPIO and console paths and exit with the result.
+
+extern crate capsules;
+#[macro_use(debug)]
+extern crate kernel;
+extern crate host;
+
+use capsules::console::{self, Console};
+use capsules::timer::TimerDriver;
+use capsules::virtual_alarm::{MuxAlarm, VirtualMuxAlarm};
+use capsules::virtual_i2c::MuxI2C;
+use capsules::virtual_spi::{MuxSpiMaster, VirtualSpiMasterDevice};
+use host::leak;
+use kernel::Platform;
+use kernel::hil;
+use kernel::hil::spi::SpiMaster;
+
+mod selftest;
+
+static mut SPI_READ_BUF: [u8; 64] = [0; 64];
+static mut SPI_WRITE_BUF: [u8; 64] = [0; 64];
+
+/// Address of the simulated register file on the I2C bus.
+const I2C_MEMORY_ADDRESS: u8 = 0x50;
+
+struct Host {
+    console: &'static Console<'static, host::uart::Uart>,
+    gpio: &'static capsules::gpio::GPIO<'static, host::gpio::Pin>,
+    timer: &'static TimerDriver<'static, VirtualMuxAlarm<'static, host::alarm::Alarm>>,
+    spi: &'static capsules::spi::Spi<'static, VirtualSpiMasterDevice<'static, host::spi::Spi>>,
+    led: &'static capsules::led::LED<'static, host::gpio::Pin>,
+    button: &'static capsules::button::Button<'static, host::gpio::Pin>,
+    ipc: kernel::ipc::IPC,
+}
+
+impl Platform for Host {
+    fn with_driver<F, R>(&self, driver_num: usize, f: F) -> R
+        where F: FnOnce(Option<&kernel::Driver>) -> R
+    {
+        match driver_num {
+            0 => f(Some(self.console)),
+            1 => f(Some(self.gpio)),
+
+            3 => f(Some(self.timer)),
+            4 => f(Some(self.spi)),
+
+            8 => f(Some(self.led)),
+            9 => f(Some(self.button)),
+
+            0xff => f(Some(&self.ipc)),
+            _ => f(None),
+        }
+    }
+}
+
+fn main() {
+    unsafe { run() }
+}
+
+unsafe fn run() {
+    let mut chip = host::chip::Host::new();
+
+    let console = leak(Console::new(chip.uart,
+                                    115200,
+                                    &mut console::WRITE_BUF,
+                                    kernel::Container::create()));
+    hil::uart::UART::set_client(chip.uart, console);
+    kernel::container::add_reset_client(console);
+
+    let mux_alarm = leak(MuxAlarm::new(chip.alarm));
+    chip.alarm.set_client(mux_alarm);
+
+    // Timer
+    let virtual_alarm1 = leak(VirtualMuxAlarm::new(mux_alarm));
+    let timer = leak(TimerDriver::new(virtual_alarm1, kernel::Container::create()));
+    virtual_alarm1.set_client(timer);
+    kernel::container::add_reset_client(timer);
+
+    // SPI, with the system call interface on CS0
+    let mux_spi = leak(MuxSpiMaster::new(chip.spi));
+    chip.spi.set_client(mux_spi);
+    chip.spi.init();
+
+    let syscall_spi_device = leak(VirtualSpiMasterDevice::new(mux_spi, 0));
+    let spi_syscalls = leak(capsules::spi::Spi::new(syscall_spi_device));
+    spi_syscalls.config_buffers(&mut SPI_READ_BUF, &mut SPI_WRITE_BUF);
+    syscall_spi_device.set_client(spi_syscalls);
+    kernel::container::add_reset_client(spi_syscalls);
+
+    // I2C, with a register file standing in for a sensor or EEPROM
+    let i2c_memory = leak(host::i2c::RegisterFile::new());
+    chip.i2c.add_device(I2C_MEMORY_ADDRESS, i2c_memory);
+    let mux_i2c = leak(MuxI2C::new(chip.i2c));
+    chip.i2c.set_master_client(mux_i2c);
+
+    // LEDs
+    let led_pins = leak([&chip.pins[0], &chip.pins[1], &chip.pins[2]]);
+    let led = leak(capsules::led::LED::new(led_pins, capsules::led::ActivationMode::ActiveLow));
+
+    // BUTTONs
+    let button_pins = leak([&chip.pins[3]]);
+    let button = leak(capsules::button::Button::new(button_pins, kernel::Container::create()));
+    for btn in button_pins.iter() {
+        btn.set_client(button);
+    }
+
+    // GPIO driver controlling the remaining pins, except the last one, which
+    // the self-test uses
+    let gpio_pins = leak([&chip.pins[4], &chip.pins[5], &chip.pins[6], &chip.pins[7]]);
+    let gpio = leak(capsules::gpio::GPIO::new(gpio_pins));
+    for pin in gpio_pins.iter() {
+        pin.set_client(gpio);
+    }
+    kernel::container::add_reset_client(gpio);
+
+    let board = Host {
+        console: console,
+        gpio: gpio,
+        timer: timer,
+        spi: spi_syscalls,
+        led: led,
+        button: button,
+        ipc: kernel::ipc::IPC::new(),
+    };
+
+    board.console.initialize();
+    // Attach the kernel debug interface to this console
+    let kc = leak(capsules::console::App::default());
+    kernel::debug::assign_console_driver(Some(board.console), kc);
+
+    if std::env::args().any(|arg| arg == "--self-test") {
+        let test_pin = &chip.pins[host::chip::NUM_PINS - 1];
+        selftest::start(mux_alarm,
+                        mux_spi,
+                        mux_i2c,
+                        I2C_MEMORY_ADDRESS,
+                        test_pin,
+                        chip.uart,
+                        board.console);
+    }
+
+    // Processes cannot run on the host
+    static mut PROCESSES: [Option<kernel::process::Process<'static>>; 0] = [];
+    let timeslices: [u32; 0] = [];
+    let scheduler = kernel::scheduler::RoundRobin::new(&timeslices);
+
+    debug!("Initialization complete. Entering main loop");
+    kernel::main(&board, &mut chip, &mut PROCESSES, &board.ipc, &scheduler);
+}
@@ -0,0 +1,224 @@
+//! Checks that the virtualized alarm, SPI and I2C capsules, the GPIO
+//! interrupt path and the console work against the simulated peripherals.
+//!
+//! The console check reads `CONSOLE_INPUT` from stdin, which `make test`
+//! supplies, and echoes it through the console to stdout.
+//!
+//! Each check starts an asynchronous operation and passes from its
+//! completion callback. The process exits with status 0 once every check
+//! has passed, or with status 1 on the first failure or after a timeout.
+
+use capsules::console::Console;
+use capsules::virtual_alarm::{MuxAlarm, VirtualMuxAlarm};
+use capsules::virtual_i2c::{I2CDevice, MuxI2C};
+use capsules::virtual_spi::{MuxSpiMaster, VirtualSpiMasterDevice};
+use host::alarm::Alarm;
+use host::gpio::Pin;
+use host::leak;
+use host::spi::Spi;
+use host::uart::Uart;
+use kernel::hil::gpio::{self, Pin as PinTrait};
+use kernel::hil::i2c::{self, I2CClient, I2CDevice as I2CDeviceTrait};
+use kernel::hil::spi::{self, SpiMasterDevice};
+use kernel::hil::time::{self, Alarm as AlarmTrait, Frequency};
+use kernel::hil::uart::{self, UART};
+use std::cell::Cell;
+use std::process;
+
+const ALARMS_PASSED: u8 = 1 << 0;
+const SPI_PASSED: u8 = 1 << 1;
+const I2C_PASSED: u8 = 1 << 2;
+const GPIO_PASSED: u8 = 1 << 3;
+const CONSOLE_PASSED: u8 = 1 << 4;
+const ALL_PASSED: u8 = ALARMS_PASSED | SPI_PASSED | I2C_PASSED | GPIO_PASSED | CONSOLE_PASSED;
+
+const TIMEOUT_ALARM: usize = 0;
+const SHORT_ALARM: usize = 1;
+const LONG_ALARM: usize = 2;
+
+/// Chip select the SPI check uses, distinct from the system call device.
+const SPI_CHIP_SELECT: u8 = 1;
+
+/// Register the I2C check writes to and reads back from.
+const I2C_REGISTER: u8 = 0x10;
+
+/// Input the console check expects on stdin.
+pub const CONSOLE_INPUT: &'static [u8] = b"ping";
+
+static mut SPI_WRITE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+static mut SPI_READ: [u8; 8] = [0; 8];
+static mut I2C_BUF: [u8; 3] = [I2C_REGISTER, 0xaa, 0xbb];
+static mut CONSOLE_BUF: [u8; 4] = [0; 4];
+
+struct SelfTest {
+    passed: Cell<u8>,
+    short_alarm_fired: Cell<bool>,
+    i2c: &'static I2CDevice<'static>,
+    i2c_written: Cell<bool>,
+    console_received: Cell<bool>,
+}
+
+impl SelfTest {
+    fn pass(&self, check: u8) {
+        self.passed.set(self.passed.get() | check);
+        if self.passed.get() == ALL_PASSED {
+            println!("self-test: passed");
+            process::exit(0);
+        }
+    }
+
+    fn fail(&self, reason: &str) {
+        println!("self-test: FAILED: {}", reason);
+        process::exit(1);
+    }
+
+    fn alarm_fired(&self, id: usize) {
+        match id {
+            TIMEOUT_ALARM => self.fail("timed out"),
+            SHORT_ALARM => self.short_alarm_fired.set(true),
+            _ => {
+                if self.short_alarm_fired.get() {
+                    self.pass(ALARMS_PASSED);
+                } else {
+                    self.fail("virtual alarms fired out of order");
+                }
+            }
+        }
+    }
+}
+
+/// Forwards an alarm to the test, tagged with which alarm it was.
+struct AlarmCheck {
+    id: usize,
+    test: &'static SelfTest,
+}
+
+impl time::Client for AlarmCheck {
+    fn fired(&self) {
+        self.test.alarm_fired(self.id);
+    }
+}
+
+impl spi::SpiMasterClient for SelfTest {
+    fn read_write_done(&self,
+                       write_buffer: &'static mut [u8],
+                       read_buffer: Option<&'static mut [u8]>,
+                       len: usize) {
+        match read_buffer {
+            Some(ref read) if len == write_buffer.len() && read[..len] == write_buffer[..len] => {
+                self.pass(SPI_PASSED)
+            }
+            _ => self.fail("SPI loopback returned different data"),
+        }
+    }
+}
+
+impl I2CClient for SelfTest {
+    fn command_complete(&self, buffer: &'static mut [u8], error: i2c::Error) {
+        if error != i2c::Error::CommandComplete {
+            self.fail("I2C transfer failed");
+            return;
+        }
+
+        if !self.i2c_written.get() {
+            // Read back the two registers that were just written
+            self.i2c_written.set(true);
+            buffer[0] = I2C_REGISTER;
+            self.i2c.write_read(buffer, 1, 2);
+        } else if buffer[..2] == [0xaa, 0xbb] {
+            self.pass(I2C_PASSED);
+        } else {
+            self.fail("I2C register file returned different data");
+        }
+    }
+}
+
+impl gpio::Client for SelfTest {
+    fn fired(&self, _: usize) {
+        self.pass(GPIO_PASSED);
+    }
+}
+
+/// Stands in for the console as the UART's client, to receive the console
+/// check's input and see it written back out. Transmissions are passed on to
+/// the console.
+struct ConsoleCheck {
+    test: &'static SelfTest,
+    console: &'static Console<'static, Uart>,
+}
+
+impl uart::Client for ConsoleCheck {
+    fn transmit_complete(&self, buffer: &'static mut [u8], error: uart::Error) {
+        // The echo is the only output that contains the input
+        let echoed = self.test.console_received.get() &&
+                     buffer.windows(CONSOLE_INPUT.len()).any(|bytes| bytes == CONSOLE_INPUT);
+        uart::Client::transmit_complete(self.console, buffer, error);
+        if echoed {
+            self.test.pass(CONSOLE_PASSED);
+        }
+    }
+
+    fn receive_complete(&self, buffer: &'static mut [u8], rx_len: usize, error: uart::Error) {
+        if error != uart::Error::CommandComplete || &buffer[..rx_len] != CONSOLE_INPUT {
+            self.test.fail("console received different data");
+            return;
+        }
+        self.test.console_received.set(true);
+        debug!("self-test: console received {}",
+               String::from_utf8_lossy(&buffer[..rx_len]));
+    }
+}
+
+fn start_alarm(mux: &'static MuxAlarm<'static, Alarm>,
+               test: &'static SelfTest,
+               id: usize,
+               ms: u32) {
+    let alarm = leak(VirtualMuxAlarm::new(mux));
+    alarm.set_client(leak(AlarmCheck { id: id, test: test }));
+    let tics = ms * <<Alarm as AlarmTrait>::Frequency as Frequency>::frequency() / 1000;
+    alarm.set_alarm(alarm.now().wrapping_add(tics));
+}
+
+pub unsafe fn start(mux_alarm: &'static MuxAlarm<'static, Alarm>,
+                    mux_spi: &'static MuxSpiMaster<'static, Spi>,
+                    mux_i2c: &'static MuxI2C<'static>,
+                    i2c_address: u8,
+                    pin: &'static Pin,
+                    uart: &'static Uart,
+                    console: &'static Console<'static, Uart>) {
+    debug!("Running self-test");
+
+    let i2c: &'static I2CDevice = leak(I2CDevice::new(mux_i2c, i2c_address));
+    let test: &'static SelfTest = leak(SelfTest {
+        passed: Cell::new(0),
+        short_alarm_fired: Cell::new(false),
+        i2c: i2c,
+        i2c_written: Cell::new(false),
+        console_received: Cell::new(false),
+    });
+
+    // Alarms set out of order must still fire in order
+    start_alarm(mux_alarm, test, TIMEOUT_ALARM, 2000);
+    start_alarm(mux_alarm, test, LONG_ALARM, 20);
+    start_alarm(mux_alarm, test, SHORT_ALARM, 10);
+
+    let spi = leak(VirtualSpiMasterDevice::new(mux_spi, SPI_CHIP_SELECT));
+    spi.set_client(test);
+    spi.configure(spi::ClockPolarity::IdleLow, spi::ClockPhase::SampleLeading, 1000000);
+    spi.read_write_bytes(&mut SPI_WRITE, Some(&mut SPI_READ), SPI_WRITE.len());
+
+    i2c.set_client(test);
+    i2c.enable();
+    i2c.write(&mut I2C_BUF, 3);
+
+    pin.make_input();
+    pin.set_client(test);
+    pin.enable_interrupt(0, gpio::InterruptMode::RisingEdge);
+    pin.set_input_level(true);
+
+    uart.set_client(leak(ConsoleCheck {
+        test: test,
+        console: console,
+    }));
+    uart.receive(&mut CONSOLE_BUF, CONSOLE_INPUT.len());
+}
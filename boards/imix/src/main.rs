@@ -43,6 +43,9 @@ struct Imix {
     button: &'static capsules::button::Button<'static, sam4l::gpio::GPIOPin>,
     spi: &'static capsules::spi::Spi<'static, VirtualSpiMasterDevice<'static, sam4l::spi::Spi>>,
     ipc: kernel::ipc::IPC,
+    process_stats: &'static capsules::process_stats::ProcessStats,
+    process_control: &'static capsules::process_control::ProcessControl,
+    app_loader: &'static capsules::app_loader::AppLoader<'static, sam4l::flashcalw::FLASHCALW>,
     fxos8700_cq: &'static capsules::fxos8700_cq::Fxos8700cq<'static>,
     radio: &'static capsules::radio::RadioDriver<'static,
                                                  capsules::rf233::RF233<'static,
@@ -83,6 +86,9 @@ impl kernel::Platform for Imix {
             9 => f(Some(self.button)),
             10 => f(Some(self.si7021)),
             11 => f(Some(self.fxos8700_cq)),
+            15 => f(Some(self.process_stats)),
+            16 => f(Some(self.process_control)),
+            17 => f(Some(self.app_loader)),
             154 => f(Some(self.radio)),
             0xff => f(Some(&self.ipc)),
             _ => f(None),
@@ -175,8 +181,9 @@ pub unsafe fn reset_handler() {
                      115200,
                      &mut capsules::console::WRITE_BUF,
                      kernel::Container::create()),
-        224/8);
+        256/8);
     hil::uart::UART::set_client(&sam4l::usart::USART3, console);
+    kernel::container::add_reset_client(console);
     console.initialize();
 
     // Attach the kernel debug interface to this console
@@ -205,6 +212,20 @@ pub unsafe fn reset_handler() {
         TimerDriver::new(virtual_alarm1, kernel::Container::create()),
         12);
     virtual_alarm1.set_client(timer);
+    kernel::container::add_reset_client(timer);
+
+    // Restart faulted processes once their backoff expires. Only boards with
+    // RESTART fault responses need this.
+    let restart_virtual_alarm = static_init!(
+        VirtualMuxAlarm<'static, sam4l::ast::Ast>,
+        VirtualMuxAlarm::new(mux_alarm),
+        24);
+    let restart_alarm = static_init!(
+        kernel::restart::RestartAlarm<'static, VirtualMuxAlarm<'static, sam4l::ast::Ast>>,
+        kernel::restart::RestartAlarm::new(restart_virtual_alarm),
+        4);
+    restart_virtual_alarm.set_client(restart_alarm);
+    kernel::restart::set_restart_timer(restart_alarm);
 
     // # I2C Sensors
 
@@ -226,6 +247,7 @@ pub unsafe fn reset_handler() {
         384/8);
     isl29035_i2c.set_client(isl29035);
     isl29035_virtual_alarm.set_client(isl29035);
+    kernel::container::add_reset_client(isl29035);
 
     // Set up an SPI MUX, so there can be multiple clients
     let mux_spi = static_init!(
@@ -255,6 +277,7 @@ pub unsafe fn reset_handler() {
     static mut SPI_WRITE_BUF: [u8; 64] = [0; 64];
     spi_syscalls.config_buffers(&mut SPI_READ_BUF, &mut SPI_WRITE_BUF);
     syscall_spi_device.set_client(spi_syscalls);
+    kernel::container::add_reset_client(spi_syscalls);
 
 
     // Configure the SI7021, device address 0x40
@@ -269,6 +292,7 @@ pub unsafe fn reset_handler() {
         352/8);
     si7021_i2c.set_client(si7021);
     si7021_alarm.set_client(si7021);
+    kernel::container::add_reset_client(si7021);
 
     // Create a second virtualized SPI client, for the RF233
     let rf233_spi = static_init!(VirtualSpiMasterDevice<'static, sam4l::spi::Spi>,
@@ -293,6 +317,7 @@ pub unsafe fn reset_handler() {
         capsules::fxos8700_cq::Fxos8700cq::new(fx0_i2c, &mut capsules::fxos8700_cq::BUF),
         352/8);
     fx0_i2c.set_client(fx0);
+    kernel::container::add_reset_client(fx0);
 
     // Clear sensors enable pin to enable sensor rail
     // sam4l::gpio::PC[16].enable_output();
@@ -306,6 +331,7 @@ pub unsafe fn reset_handler() {
         capsules::adc::ADC::new(&mut sam4l::adc::ADC),
         224/8);
     sam4l::adc::ADC.set_client(adc);
+    kernel::container::add_reset_client(adc);
 
     // # GPIO
     // set GPIO driver controlling remaining GPIO pins
@@ -329,6 +355,7 @@ pub unsafe fn reset_handler() {
     for pin in gpio_pins.iter() {
         pin.set_client(gpio);
     }
+    kernel::container::add_reset_client(gpio);
 
     // # LEDs
     let led_pins = static_init!(
@@ -355,6 +382,48 @@ pub unsafe fn reset_handler() {
         btn.set_client(button);
     }
 
+    // # PROCESS STATISTICS
+
+    // Only the app named "monitor" may read other processes' statistics, once
+    // APP_SIGNING_KEY in load_processes is set
+    let process_stats = static_init!(
+        capsules::process_stats::ProcessStats,
+        capsules::process_stats::ProcessStats::new(kernel::Container::create(), "monitor"),
+        12);
+
+    // # PROCESS CONTROL
+
+    // The same app may also stop, resume and restart other processes
+    let process_control = static_init!(
+        capsules::process_control::ProcessControl,
+        capsules::process_control::ProcessControl::new(kernel::Container::create(), "monitor"),
+        12);
+
+    // # APP LOADER
+
+    extern "C" {
+        /// Beginning of the ROM region containing app images.
+        static _sapps: u8;
+        /// End of the ROM region containing app images.
+        static _eapps: u8;
+    }
+
+    // The app named "installer" may write new apps to flash and start them,
+    // once APP_SIGNING_KEY in load_processes is set
+    static mut APP_LOADER_BUF: [u8; 512] = [0; 512];
+    sam4l::flashcalw::FLASH_CONTROLLER.configure();
+    let app_loader = static_init!(
+        capsules::app_loader::AppLoader<'static, sam4l::flashcalw::FLASHCALW>,
+        capsules::app_loader::AppLoader::new(&sam4l::flashcalw::FLASH_CONTROLLER,
+                                             kernel::Container::create(),
+                                             "installer",
+                                             &mut APP_LOADER_BUF,
+                                             &_sapps as *const u8 as usize,
+                                             &_eapps as *const u8 as usize),
+        56);
+    hil::flash::Flash::set_client(&sam4l::flashcalw::FLASH_CONTROLLER, app_loader);
+    kernel::container::add_reset_client(app_loader);
+
     rf233_spi.set_client(rf233);
     rf233.initialize(&mut RF233_BUF, &mut RF233_REG_WRITE, &mut RF233_REG_READ);
 
@@ -367,6 +436,7 @@ pub unsafe fn reset_handler() {
     radio_capsule.config_buffer(&mut RADIO_BUF);
     rf233.set_transmit_client(radio_capsule);
     rf233.set_receive_client(radio_capsule, &mut RF233_RX_BUF);
+    kernel::container::add_reset_client(radio_capsule);
 
     let imix = Imix {
         console: console,
@@ -379,6 +449,9 @@ pub unsafe fn reset_handler() {
         button: button,
         spi: spi_syscalls,
         ipc: kernel::ipc::IPC::new(),
+        process_stats: process_stats,
+        process_control: process_control,
+        app_loader: app_loader,
         fxos8700_cq: fx0,
         radio: radio_capsule,
     };
@@ -392,8 +465,12 @@ pub unsafe fn reset_handler() {
     rf233.set_address(0x1008);
     rf233.start();
 
+    // Run processes in turn, 10ms each
+    let timeslices = [kernel::scheduler::DEFAULT_TIMESLICE_US; 4];
+    let scheduler = kernel::scheduler::RoundRobin::new(&timeslices);
+
     debug!("Initialization complete. Entering main loop");
-    kernel::main(&imix, &mut chip, load_processes(), &imix.ipc);
+    kernel::main(&imix, &mut chip, load_processes(), &imix.ipc, &scheduler);
 }
 
 unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'static>>] {
@@ -402,15 +479,30 @@ unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'sta
         static _sapps: u8;
     }
 
-    const NUM_PROCS: usize = 2;
+    const NUM_PROCS: usize = 4;
+
+    // how should the kernel respond when each process faults
+    const RESTART: kernel::process::FaultResponse =
+        kernel::process::FaultResponse::Restart(kernel::process::RestartPolicy {
+            max_restarts: 3,
+            backoff_ms: 1000,
+        });
+    const FAULT_RESPONSES: [kernel::process::FaultResponse; NUM_PROCS] =
+        [RESTART, RESTART, RESTART, RESTART];
 
-    // how should the kernel respond when a process faults
-    const FAULT_RESPONSE: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    // Public key that apps must be signed with, as the big-endian X and Y
+    // coordinates of a P-256 point. With `None`, unsigned apps are loaded too.
+    const APP_SIGNING_KEY: Option<&'static [u8; 64]> = None;
 
     #[link_section = ".app_memory"]
     static mut APP_MEMORY: [u8; 16384] = [0; 16384];
 
-    static mut PROCESSES: [Option<kernel::process::Process<'static>>; NUM_PROCS] = [None, None];
+    static mut PROCESSES: [Option<kernel::process::Process<'static>>; NUM_PROCS] =
+        [None, None, None, None];
+
+    if let Some(key) = APP_SIGNING_KEY {
+        kernel::process::set_app_signing_key(key);
+    }
 
     let mut apps_in_flash_ptr = &_sapps as *const u8;
     let mut app_memory_ptr = APP_MEMORY.as_mut_ptr();
@@ -420,17 +512,21 @@ unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'sta
             kernel::process::Process::create(apps_in_flash_ptr,
                                              app_memory_ptr,
                                              app_memory_size,
-                                             FAULT_RESPONSE);
+                                             FAULT_RESPONSES[i]);
 
-        if process.is_none() {
-            break;
+        match process {
+            Ok(process) => PROCESSES[i] = Some(process),
+            Err(kernel::process::LoadError::NoValidHeader) => break,
+            Err(err) => debug!("App {} failed to load: {:?}", i, err),
         }
 
-        PROCESSES[i] = process;
         apps_in_flash_ptr = apps_in_flash_ptr.offset(flash_offset as isize);
         app_memory_ptr = app_memory_ptr.offset(memory_offset as isize);
         app_memory_size -= memory_offset;
     }
 
+    // Apps installed at runtime use the remaining memory
+    kernel::process::set_dynamic_app_memory(app_memory_ptr, app_memory_size, RESTART);
+
     &mut PROCESSES
 }
@@ -1,4 +1,6 @@
 use core::fmt::*;
+use kernel::debug;
+use kernel::hil::gpio::Pin;
 use kernel::hil::uart::{self, UART};
 use nrf51;
 
@@ -32,6 +34,30 @@ impl Write for Writer {
 }
 
 
+#[cfg(not(test))]
+#[no_mangle]
+#[lang="panic_fmt"]
+pub unsafe extern "C" fn panic_fmt(args: Arguments, file: &'static str, line: u32) -> ! {
+    let writer = &mut WRITER;
+    debug::panic(writer, args, file, line);
+
+    // blink the panic signal
+    let led0 = &nrf51::gpio::PORT[::LED1_PIN];
+    let led1 = &nrf51::gpio::PORT[::LED2_PIN];
+    led0.make_output();
+    led1.make_output();
+    loop {
+        for _ in 0..100000 {
+            led0.set();
+            led1.set();
+        }
+        for _ in 0..100000 {
+            led0.clear();
+            led1.clear();
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! print {
         ($($arg:tt)*) => (
@@ -73,14 +73,23 @@ unsafe fn load_process() -> &'static mut [Option<kernel::process::Process<'stati
 
     const NUM_PROCS: usize = 1;
 
-    // how should the kernel respond when a process faults
-    const FAULT_RESPONSE: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    // how should the kernel respond when each process faults
+    const PANIC: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    const FAULT_RESPONSES: [kernel::process::FaultResponse; NUM_PROCS] = [PANIC];
+
+    // Public key that apps must be signed with, as the big-endian X and Y
+    // coordinates of a P-256 point. With `None`, unsigned apps are loaded too.
+    const APP_SIGNING_KEY: Option<&'static [u8; 64]> = None;
 
     #[link_section = ".app_memory"]
     static mut APP_MEMORY: [u8; 8192] = [0; 8192];
 
     static mut PROCESSES: [Option<kernel::process::Process<'static>>; NUM_PROCS] = [None];
 
+    if let Some(key) = APP_SIGNING_KEY {
+        kernel::process::set_app_signing_key(key);
+    }
+
     let mut apps_in_flash_ptr = &_sapps as *const u8;
     let mut app_memory_ptr = APP_MEMORY.as_mut_ptr();
     let mut app_memory_size = APP_MEMORY.len();
@@ -89,13 +98,14 @@ unsafe fn load_process() -> &'static mut [Option<kernel::process::Process<'stati
             kernel::process::Process::create(apps_in_flash_ptr,
                                              app_memory_ptr,
                                              app_memory_size,
-                                             FAULT_RESPONSE);
+                                             FAULT_RESPONSES[i]);
 
-        if process.is_none() {
-            break;
+        match process {
+            Ok(process) => PROCESSES[i] = Some(process),
+            Err(kernel::process::LoadError::NoValidHeader) => break,
+            Err(err) => debug!("App {} failed to load: {:?}", i, err),
         }
 
-        PROCESSES[i] = process;
         apps_in_flash_ptr = apps_in_flash_ptr.offset(flash_offset as isize);
         app_memory_ptr = app_memory_ptr.offset(memory_offset as isize);
         app_memory_size -= memory_offset;
@@ -187,6 +197,7 @@ pub unsafe fn reset_handler() {
     for pin in gpio_pins.iter() {
         pin.set_client(gpio);
     }
+    kernel::container::add_reset_client(gpio);
 
     nrf51::uart::UART0.configure(Pinmux::new(9),
                                  Pinmux::new(11),
@@ -198,8 +209,9 @@ pub unsafe fn reset_handler() {
                                         115200,
                                         &mut capsules::console::WRITE_BUF,
                                         kernel::Container::create()),
-        224/8);
+        256/8);
     UART::set_client(&nrf51::uart::UART0, console);
+    kernel::container::add_reset_client(console);
     console.initialize();
 
     // Attach the kernel debug interface to this console
@@ -225,6 +237,7 @@ pub unsafe fn reset_handler() {
                          kernel::Container::create()),
         12);
     virtual_alarm1.set_client(timer);
+    kernel::container::add_reset_client(timer);
 
     // Start all of the clocks. Low power operation will require a better
     // approach than this.
@@ -251,38 +264,15 @@ pub unsafe fn reset_handler() {
     chip.systick().reset();
     chip.systick().enable(true);
 
+    // Run processes in turn, 10ms each
+    let timeslices = [kernel::scheduler::DEFAULT_TIMESLICE_US; 1];
+    let scheduler = kernel::scheduler::RoundRobin::new(&timeslices);
+
     debug!("Initialization complete. Entering main loop");
     kernel::main(&platform,
                  &mut chip,
                  load_process(),
-                 &kernel::ipc::IPC::new());
-
-}
-
+                 &kernel::ipc::IPC::new(),
+                 &scheduler);
 
-use core::fmt::Arguments;
-#[cfg(not(test))]
-#[lang="panic_fmt"]
-#[no_mangle]
-pub unsafe extern "C" fn rust_begin_unwind(_args: &Arguments,
-                                           _file: &'static str,
-                                           _line: usize)
-                                           -> ! {
-    use kernel::hil::gpio::Pin;
-
-    let led0 = &nrf51::gpio::PORT[LED1_PIN];
-    let led1 = &nrf51::gpio::PORT[LED2_PIN];
-
-    led0.make_output();
-    led1.make_output();
-    loop {
-        for _ in 0..100000 {
-            led0.set();
-            led1.set();
-        }
-        for _ in 0..100000 {
-            led0.clear();
-            led1.clear();
-        }
-    }
 }
@@ -50,14 +50,23 @@ unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'sta
 
     const NUM_PROCS: usize = 2;
 
-    // how should the kernel respond when a process faults
-    const FAULT_RESPONSE: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    // how should the kernel respond when each process faults
+    const PANIC: kernel::process::FaultResponse = kernel::process::FaultResponse::Panic;
+    const FAULT_RESPONSES: [kernel::process::FaultResponse; NUM_PROCS] = [PANIC, PANIC];
+
+    // Public key that apps must be signed with, as the big-endian X and Y
+    // coordinates of a P-256 point. With `None`, unsigned apps are loaded too.
+    const APP_SIGNING_KEY: Option<&'static [u8; 64]> = None;
 
     #[link_section = ".app_memory"]
     static mut APP_MEMORY: [u8; 16384] = [0; 16384];
 
     static mut PROCESSES: [Option<kernel::process::Process<'static>>; NUM_PROCS] = [None, None];
 
+    if let Some(key) = APP_SIGNING_KEY {
+        kernel::process::set_app_signing_key(key);
+    }
+
     let mut apps_in_flash_ptr = &_sapps as *const u8;
     let mut app_memory_ptr = APP_MEMORY.as_mut_ptr();
     let mut app_memory_size = APP_MEMORY.len();
@@ -66,13 +75,14 @@ unsafe fn load_processes() -> &'static mut [Option<kernel::process::Process<'sta
             kernel::process::Process::create(apps_in_flash_ptr,
                                              app_memory_ptr,
                                              app_memory_size,
-                                             FAULT_RESPONSE);
+                                             FAULT_RESPONSES[i]);
 
-        if process.is_none() {
-            break;
+        match process {
+            Ok(process) => PROCESSES[i] = Some(process),
+            Err(kernel::process::LoadError::NoValidHeader) => break,
+            Err(err) => debug!("App {} failed to load: {:?}", i, err),
         }
 
-        PROCESSES[i] = process;
         apps_in_flash_ptr = apps_in_flash_ptr.offset(flash_offset as isize);
         app_memory_ptr = app_memory_ptr.offset(memory_offset as isize);
         app_memory_size -= memory_offset;
@@ -236,8 +246,9 @@ pub unsafe fn reset_handler() {
                      115200,
                      &mut console::WRITE_BUF,
                      kernel::Container::create()),
-        224/8);
+        256/8);
     hil::uart::UART::set_client(&usart::USART3, console);
+    kernel::container::add_reset_client(console);
 
     // Create the Nrf51822Serialization driver for passing BLE commands
     // over UART to the nRF51822 radio.
@@ -248,6 +259,7 @@ pub unsafe fn reset_handler() {
                                    &mut nrf51822_serialization::READ_BUF),
         608/8);
     hil::uart::UART::set_client(&usart::USART2, nrf_serialization);
+    kernel::container::add_reset_client(nrf_serialization);
 
     let ast = &sam4l::ast::AST;
 
@@ -271,6 +283,7 @@ pub unsafe fn reset_handler() {
         480/8);
     tmp006_i2c.set_client(tmp006);
     sam4l::gpio::PA[9].set_client(tmp006);
+    kernel::container::add_reset_client(tmp006);
 
     // Configure the ISL29035, device address 0x44
     let isl29035_i2c = static_init!(I2CDevice, I2CDevice::new(mux_i2c, 0x44), 32);
@@ -287,6 +300,7 @@ pub unsafe fn reset_handler() {
         384/8);
     isl29035_i2c.set_client(isl29035);
     isl29035_virtual_alarm.set_client(isl29035);
+    kernel::container::add_reset_client(isl29035);
 
     let virtual_alarm1 = static_init!(
         VirtualMuxAlarm<'static, sam4l::ast::Ast>,
@@ -297,6 +311,7 @@ pub unsafe fn reset_handler() {
         TimerDriver::new(virtual_alarm1, kernel::Container::create()),
         12);
     virtual_alarm1.set_client(timer);
+    kernel::container::add_reset_client(timer);
 
     // Initialize and enable SPI HAL
     // Set up an SPI MUX, so there can be multiple clients
@@ -324,6 +339,7 @@ pub unsafe fn reset_handler() {
 
     spi_syscalls.config_buffers(&mut SPI_READ_BUF, &mut SPI_WRITE_BUF);
     syscall_spi_device.set_client(spi_syscalls);
+    kernel::container::add_reset_client(spi_syscalls);
 
     // LEDs
     let led_pins = static_init!(
@@ -341,6 +357,7 @@ pub unsafe fn reset_handler() {
         capsules::adc::ADC::new(&mut sam4l::adc::ADC),
         224/8);
     sam4l::adc::ADC.set_client(adc);
+    kernel::container::add_reset_client(adc);
 
     // RNG
     //
@@ -374,6 +391,7 @@ pub unsafe fn reset_handler() {
     for pin in gpio_pins.iter() {
         pin.set_client(gpio);
     }
+    kernel::container::add_reset_client(gpio);
 
     // Note: The following GPIO pins aren't assigned to anything:
     // &sam4l::gpio::PC[19] // !ENSEN
@@ -439,6 +457,10 @@ pub unsafe fn reset_handler() {
     chip.mpu().enable_mpu();
 
 
+    // Run processes in turn, 10ms each
+    let timeslices = [kernel::scheduler::DEFAULT_TIMESLICE_US; 2];
+    let scheduler = kernel::scheduler::RoundRobin::new(&timeslices);
+
     debug!("Initialization complete. Entering main loop");
-    kernel::main(&firestorm, &mut chip, load_processes(), &firestorm.ipc);
+    kernel::main(&firestorm, &mut chip, load_processes(), &firestorm.ipc, &scheduler);
 }
@@ -5,6 +5,7 @@
 
 use core::cell::Cell;
 use kernel::{AppId, Callback, Driver, ReturnCode};
+use kernel::container::ResetClient;
 use kernel::hil::adc::{Client, AdcSingle};
 
 pub struct ADC<'a, A: AdcSingle + 'a> {
@@ -40,12 +41,31 @@ impl<'a, A: AdcSingle + 'a> Client for ADC<'a, A> {
     }
 }
 
+impl<'a, A: AdcSingle + 'a> ResetClient for ADC<'a, A> {
+    fn containers_reset(&self, appid: AppId) {
+        if self.callback.get().map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+            self.callback.set(None);
+        }
+    }
+}
+
 impl<'a, A: AdcSingle + 'a> Driver for ADC<'a, A> {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                if self.callback.get().map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                    self.callback.set(None);
+                }
+                ReturnCode::SUCCESS
+            }
+
             // subscribe to ADC sample done
             0 => {
-                self.callback.set(Some(callback));
+                self.callback.set(callback);
                 ReturnCode::SUCCESS
             }
 
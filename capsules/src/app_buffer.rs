@@ -0,0 +1,33 @@
+//! Filling buffers allowed by apps with records made of 32-bit words.
+
+use kernel::{AppSlice, ReturnCode, Shared};
+
+/// Writes `words` to the start of `buffer` as little-endian 32-bit words,
+/// followed by as many bytes of `tail` as fit. Fails with `ESIZE` if the
+/// words do not fit and with `ERESERVE` if there is no buffer.
+pub fn write_words(buffer: &mut Option<AppSlice<Shared, u8>>,
+                   words: &[usize],
+                   tail: &[u8])
+                   -> ReturnCode {
+    match *buffer {
+        Some(ref mut buffer) => {
+            let words_len = words.len() * 4;
+            if buffer.len() < words_len {
+                return ReturnCode::ESIZE;
+            }
+            let buf = buffer.as_mut();
+            for (i, word) in words.iter().enumerate() {
+                let word = *word as u32;
+                buf[i * 4] = word as u8;
+                buf[i * 4 + 1] = (word >> 8) as u8;
+                buf[i * 4 + 2] = (word >> 16) as u8;
+                buf[i * 4 + 3] = (word >> 24) as u8;
+            }
+            for (dest, src) in buf[words_len..].iter_mut().zip(tail.iter()) {
+                *dest = *src;
+            }
+            ReturnCode::SUCCESS
+        }
+        None => ReturnCode::ERESERVE,
+    }
+}
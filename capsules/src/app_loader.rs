@@ -0,0 +1,334 @@
+//! App Loader Capsule
+//!
+//! Installs new app images into free app flash and starts them without a
+//! reboot. Only a single installer application, identified by its package
+//! name, may use the driver; it receives images over whatever link it likes
+//! (console, radio, ...) and hands them to the kernel a page at a time. All
+//! other applications see the driver as unsupported. As any app can claim
+//! any name, the driver is unusable unless the board requires apps to be
+//! signed.
+//!
+//! An installation goes as follows:
+//!
+//!   1. Command 1 returns the chunk size, the flash page size.
+//!   2. Command 2 starts installing an image of `data` bytes. The size must be
+//!      a power of two of at least one chunk, as produced by elf2tbf, and the
+//!      image is placed in the first free flash after the installed apps. As
+//!      the MPU requires an image to be aligned to its size, installing
+//!      larger apps first leaves fewer gaps; an image that cannot be placed
+//!      without leaving a gap is refused.
+//!   3. The installer allows a buffer (allow 0) holding the next chunk of
+//!      the image and issues command 3. When the chunk is written, the
+//!      callback (subscribe 0) is called with a return code and the number
+//!      of bytes written so far. The header in the first chunk is validated
+//!      before anything is written.
+//!   4. Once the whole image is written, command 4 loads it into a free
+//!      process slot and returns the slot index. The image's hash and
+//!      signature, if the board requires one, are checked at this point.
+//!
+//! Installed images are placed where the boot-time app scan finds them, so
+//! they are loaded again after a reboot.
+
+use core::cell::Cell;
+use kernel::{AppId, AppSlice, Callback, Container, Driver, ReturnCode, Shared};
+use kernel::common::take_cell::TakeCell;
+use kernel::container::ResetClient;
+use kernel::hil::flash::{self, Flash};
+use kernel::process;
+use kernel::tbf;
+
+pub struct App {
+    callback: Option<Callback>,
+    buffer: Option<AppSlice<Shared, u8>>,
+}
+
+impl Default for App {
+    fn default() -> App {
+        App {
+            callback: None,
+            buffer: None,
+        }
+    }
+}
+
+/// An image being written to flash.
+#[derive(Clone, Copy)]
+struct Install {
+    appid: AppId,
+    address: usize,
+    size: usize,
+    written: usize,
+    /// The installer died while a chunk was being written. The installation
+    /// is dropped once the write completes.
+    abandoned: bool,
+}
+
+pub struct AppLoader<'a, F: Flash + 'a> {
+    flash: &'a F,
+    apps: Container<App>,
+    installer_name: &'static str,
+    /// One flash page, used to hold the chunk being written.
+    buffer: TakeCell<'static, [u8]>,
+    page_size: usize,
+    /// Start of the free app flash.
+    free_start: Cell<usize>,
+    flash_end: usize,
+    install: Cell<Option<Install>>,
+}
+
+impl<'a, F: Flash + 'a> AppLoader<'a, F> {
+    /// Images are installed between `free_start` and `flash_end`, after any
+    /// images already there. Flash offsets are taken to be addresses, as on
+    /// chips whose flash is mapped at address 0. `buffer` must be one flash
+    /// page long.
+    pub fn new(flash: &'a F,
+               container: Container<App>,
+               installer_name: &'static str,
+               buffer: &'static mut [u8],
+               free_start: usize,
+               flash_end: usize)
+               -> AppLoader<'a, F> {
+        // Skip the images already in flash
+        let mut free_start = free_start;
+        while free_start < flash_end {
+            let load_info = unsafe { tbf::parse_and_validate_load_info(free_start as *const u8) };
+            match load_info {
+                Some(load_info) if load_info.total_size > 0 => {
+                    free_start += load_info.total_size as usize;
+                }
+                _ => break,
+            }
+        }
+
+        AppLoader {
+            flash: flash,
+            apps: container,
+            installer_name: installer_name,
+            page_size: buffer.len(),
+            buffer: TakeCell::new(buffer),
+            free_start: Cell::new(free_start),
+            flash_end: flash_end,
+            install: Cell::new(None),
+        }
+    }
+
+    fn is_installer(&self, appid: AppId) -> bool {
+        process::is_trusted_app(appid, self.installer_name)
+    }
+
+    /// The installation started by `appid`, unless it has been abandoned.
+    fn current_install(&self, appid: AppId) -> Option<Install> {
+        match self.install.get() {
+            Some(install) if install.appid.idx() == appid.idx() && !install.abandoned => {
+                Some(install)
+            }
+            _ => None,
+        }
+    }
+
+    fn begin(&self, appid: AppId, size: usize) -> ReturnCode {
+        // A chunk still being written belongs to the current installation
+        if self.buffer.is_none() {
+            return ReturnCode::EBUSY;
+        }
+        if let Some(install) = self.install.get() {
+            if install.appid.idx() != appid.idx() {
+                return ReturnCode::EBUSY;
+            }
+        }
+        if size.count_ones() != 1 || size < self.page_size {
+            return ReturnCode::EINVAL;
+        }
+
+        let address = self.free_start.get();
+        if address % size != 0 {
+            return ReturnCode::EINVAL;
+        }
+        if address + size > self.flash_end {
+            return ReturnCode::ENOMEM;
+        }
+
+        self.install.set(Some(Install {
+            appid: appid,
+            address: address,
+            size: size,
+            written: 0,
+            abandoned: false,
+        }));
+        ReturnCode::SUCCESS
+    }
+
+    fn write_chunk(&self, appid: AppId) -> ReturnCode {
+        let install = match self.current_install(appid) {
+            Some(install) => install,
+            None => return ReturnCode::ERESERVE,
+        };
+        if install.written == install.size {
+            return ReturnCode::EALREADY;
+        }
+        let buffer = match self.buffer.take() {
+            Some(buffer) => buffer,
+            None => return ReturnCode::EBUSY,
+        };
+
+        let copied = self.apps
+            .enter(appid, |app, _| match app.buffer {
+                Some(ref slice) if slice.len() >= buffer.len() => {
+                    buffer.copy_from_slice(&slice.as_ref()[..buffer.len()]);
+                    ReturnCode::SUCCESS
+                }
+                Some(_) => ReturnCode::ESIZE,
+                None => ReturnCode::ERESERVE,
+            })
+            .unwrap_or_else(ReturnCode::from);
+        if copied != ReturnCode::SUCCESS {
+            self.buffer.replace(buffer);
+            return copied;
+        }
+
+        if install.written == 0 {
+            let valid = match tbf::parse_header(buffer) {
+                Some(load_info) => load_info.total_size as usize == install.size,
+                None => false,
+            };
+            if !valid {
+                self.buffer.replace(buffer);
+                self.install.set(None);
+                return ReturnCode::EINVAL;
+            }
+        }
+
+        self.flash.write(install.address + install.written, buffer);
+        ReturnCode::SUCCESS
+    }
+
+    fn finish(&self, appid: AppId) -> ReturnCode {
+        let install = match self.current_install(appid) {
+            Some(install) => install,
+            None => return ReturnCode::ERESERVE,
+        };
+        if install.written != install.size {
+            return ReturnCode::EBUSY;
+        }
+        self.install.set(None);
+
+        match unsafe { process::load_dynamic(install.address as *const u8) } {
+            Ok(index) => {
+                self.free_start.set(install.address + install.size);
+                ReturnCode::SuccessWithValue { value: index }
+            }
+            Err(process::LoadError::NoFreeSlot) => ReturnCode::EBUSY,
+            Err(process::LoadError::NoDynamicMemory) => ReturnCode::ENOSUPPORT,
+            Err(process::LoadError::InsufficientMemory { .. }) => ReturnCode::ENOMEM,
+            Err(_) => ReturnCode::FAIL,
+        }
+    }
+}
+
+impl<'a, F: Flash + 'a> flash::Client for AppLoader<'a, F> {
+    fn read_complete(&self, read_buffer: &'static mut [u8], _result: ReturnCode) {
+        self.buffer.replace(read_buffer);
+    }
+
+    fn write_complete(&self, write_buffer: &'static mut [u8], result: ReturnCode) {
+        self.buffer.replace(write_buffer);
+
+        let mut install = match self.install.get() {
+            Some(install) => install,
+            None => return,
+        };
+        if install.abandoned {
+            self.install.set(None);
+            return;
+        }
+        if result == ReturnCode::SUCCESS {
+            install.written += self.page_size;
+            self.install.set(Some(install));
+        }
+
+        let _ = self.apps.enter(install.appid, |app, _| {
+            app.callback.map(|mut cb| {
+                cb.schedule(isize::from(result) as usize, install.written, 0);
+            });
+        });
+    }
+
+    fn erase_complete(&self, _result: ReturnCode) {}
+}
+
+impl<'a, F: Flash + 'a> ResetClient for AppLoader<'a, F> {
+    fn containers_reset(&self, appid: AppId) {
+        // Abandon an installation by an installer that died. A chunk being
+        // written still completes, without a callback, and nothing else can
+        // be installed until it has.
+        match self.install.get() {
+            Some(mut install) if install.appid.idx() == appid.idx() => {
+                if self.buffer.is_some() {
+                    self.install.set(None);
+                } else {
+                    install.abandoned = true;
+                    self.install.set(Some(install));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a, F: Flash + 'a> Driver for AppLoader<'a, F> {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
+        if !self.is_installer(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match allow_num {
+            0 => {
+                self.apps
+                    .enter(appid, |app, _| {
+                        app.buffer = slice;
+                        ReturnCode::SUCCESS
+                    })
+                    .unwrap_or_else(ReturnCode::from)
+            }
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 appid: AppId)
+                 -> ReturnCode {
+        if !self.is_installer(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match subscribe_num {
+            0 => {
+                self.apps
+                    .enter(appid, |app, _| {
+                        app.callback = callback;
+                        ReturnCode::SUCCESS
+                    })
+                    .unwrap_or_else(ReturnCode::from)
+            }
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+
+    fn command(&self, command_num: usize, data: usize, appid: AppId) -> ReturnCode {
+        if !self.is_installer(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match command_num {
+            0 /* check if present */ => ReturnCode::SUCCESS,
+            1 /* chunk size */ => ReturnCode::SuccessWithValue { value: self.page_size },
+            2 /* begin installing an image of `data` bytes */ => self.begin(appid, data),
+            3 /* write the next chunk */ => self.write_chunk(appid),
+            4 /* load the installed image */ => self.finish(appid),
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+}
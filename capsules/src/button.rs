@@ -32,14 +32,18 @@ impl<'a, G: hil::gpio::Pin + hil::gpio::PinCtl> Button<'a, G> {
 }
 
 impl<'a, G: hil::gpio::Pin + hil::gpio::PinCtl> Driver for Button<'a, G> {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
             // set callback for pin interrupts (no affect or reliance on individual pins being
             // configured as interrupts)
             0 => {
                 self.callback
-                    .enter(callback.app_id(), |cntr, _| {
-                        cntr.0 = Some(callback);
+                    .enter(app_id, |cntr, _| {
+                        cntr.0 = callback;
                         ReturnCode::SUCCESS
                     })
                     .unwrap_or_else(|err| match err {
@@ -6,6 +6,7 @@
 use core::cell::Cell;
 use kernel::{AppId, AppSlice, Container, Callback, Shared, Driver, ReturnCode};
 use kernel::common::take_cell::TakeCell;
+use kernel::container::ResetClient;
 use kernel::hil::uart::{self, UART, Client};
 use kernel::process::Error;
 
@@ -39,6 +40,8 @@ pub struct Console<'a, U: UART + 'a> {
     uart: &'a U,
     apps: Container<App>,
     in_progress: Cell<Option<AppId>>,
+    // The app whose write is being transmitted has been reset
+    in_progress_abandoned: Cell<bool>,
     tx_buffer: TakeCell<'static, [u8]>,
     baud_rate: u32,
 }
@@ -53,6 +56,7 @@ impl<'a, U: UART> Console<'a, U> {
             uart: uart,
             apps: container,
             in_progress: Cell::new(None),
+            in_progress_abandoned: Cell::new(false),
             tx_buffer: TakeCell::new(tx_buffer),
             baud_rate: baud_rate,
         }
@@ -132,13 +136,27 @@ impl<'a, U: UART> Console<'a, U> {
     }
 }
 
+impl<'a, U: UART> ResetClient for Console<'a, U> {
+    fn containers_reset(&self, appid: AppId) {
+        // A write of the app's that is being transmitted still completes, but
+        // must not go back to the app's container
+        if self.in_progress.get().map_or(false, |id| id.idx() == appid.idx()) {
+            self.in_progress_abandoned.set(true);
+        }
+    }
+}
+
 impl<'a, U: UART> Driver for Console<'a, U> {
-    fn allow(&self, appid: AppId, allow_num: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         match allow_num {
             0 => {
                 self.apps
                     .enter(appid, |app, _| {
-                        app.read_buffer = Some(slice);
+                        app.read_buffer = slice;
                         app.read_idx = 0;
                         ReturnCode::SUCCESS
                     })
@@ -151,7 +169,7 @@ impl<'a, U: UART> Driver for Console<'a, U> {
             1 => {
                 self.apps
                     .enter(appid, |app, _| {
-                        app.write_buffer = Some(slice);
+                        app.write_buffer = slice;
                         ReturnCode::SUCCESS
                     })
                     .unwrap_or_else(|err| match err {
@@ -164,15 +182,26 @@ impl<'a, U: UART> Driver for Console<'a, U> {
         }
     }
 
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
             0 /* read line */ => {
                 // read line is not implemented for console at this time
                 ReturnCode::ENOSUPPORT
             },
             1 /* putstr/write_done */ => {
-                self.apps.enter(callback.app_id(), |app, _| {
-                    self.send_new(callback.app_id(), app, callback)
+                self.apps.enter(app_id, |app, _| {
+                    match callback {
+                        Some(callback) => self.send_new(app_id, app, callback),
+                        None => {
+                            // Any write in progress finishes without a callback
+                            app.write_callback = None;
+                            ReturnCode::SUCCESS
+                        }
+                    }
                 }).unwrap_or_else(|err| {
                     match err {
                         Error::OutOfMemory => ReturnCode::ENOMEM,
@@ -205,6 +234,10 @@ impl<'a, U: UART> Client for Console<'a, U> {
         // Either print more from the AppSlice or send a callback to the
         // application.
         self.tx_buffer.replace(buffer);
+        if self.in_progress_abandoned.get() {
+            self.in_progress_abandoned.set(false);
+            self.in_progress.set(None);
+        }
         self.in_progress.get().map(|appid| {
             self.in_progress.set(None);
             self.apps.enter(appid, |app, _| {
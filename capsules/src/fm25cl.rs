@@ -5,6 +5,7 @@ use core::cmp;
 use kernel::{AppId, AppSlice, Callback, Driver, ReturnCode, Shared};
 
 use kernel::common::take_cell::{MapCell, TakeCell};
+use kernel::container::ResetClient;
 use kernel::hil;
 
 
@@ -287,37 +288,84 @@ impl<'a, S: hil::spi::SpiMasterDevice + 'a> FM25CLClient for FM25CLDriver<'a, S>
     }
 }
 
+impl<'a, S: hil::spi::SpiMasterDevice + 'a> ResetClient for FM25CLDriver<'a, S> {
+    fn containers_reset(&self, appid: AppId) {
+        self.app_state.map(|appst| {
+            let owned = |slice: &Option<AppSlice<Shared, u8>>| {
+                slice.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx())
+            };
+            if appst.callback.map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+                appst.callback = None;
+            }
+            if owned(&appst.read_buffer) {
+                appst.read_buffer = None;
+            }
+            if owned(&appst.write_buffer) {
+                appst.write_buffer = None;
+            }
+        });
+    }
+}
+
 impl<'a, S: hil::spi::SpiMasterDevice + 'a> Driver for FM25CLDriver<'a, S> {
-    fn allow(&self, _appid: AppId, allow_num: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         match allow_num {
+            // Withdraw the read buffer
+            0 if slice.is_none() => {
+                self.app_state.map(|appst| {
+                    let owned = appst.read_buffer
+                        .as_ref()
+                        .map_or(false, |s| s.app_id().idx() == appid.idx());
+                    if owned {
+                        appst.read_buffer = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
             // Pass read buffer in from application
             0 => {
                 let appst = match self.app_state.take() {
                     None => {
                         AppState {
                             callback: None,
-                            read_buffer: Some(slice),
+                            read_buffer: slice,
                             write_buffer: None,
                         }
                     }
                     Some(mut appst) => {
-                        appst.read_buffer = Some(slice);
+                        appst.read_buffer = slice;
                         appst
                     }
                 };
                 self.app_state.replace(appst);
                 ReturnCode::SUCCESS
             }
+            // Withdraw the write buffer
+            1 if slice.is_none() => {
+                self.app_state.map(|appst| {
+                    let owned = appst.write_buffer
+                        .as_ref()
+                        .map_or(false, |s| s.app_id().idx() == appid.idx());
+                    if owned {
+                        appst.write_buffer = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
             // Pass write buffer in from application
             1 => {
                 if self.app_state.is_none() {
                     self.app_state.put(AppState {
                         callback: None,
-                        write_buffer: Some(slice),
+                        write_buffer: slice,
                         read_buffer: None,
                     });
                 } else {
-                    self.app_state.map(|appst| appst.write_buffer = Some(slice));
+                    self.app_state.map(|appst| appst.write_buffer = slice);
                 }
                 ReturnCode::SUCCESS
             }
@@ -325,17 +373,29 @@ impl<'a, S: hil::spi::SpiMasterDevice + 'a> Driver for FM25CLDriver<'a, S> {
         }
     }
 
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                self.app_state.map(|appst| {
+                    if appst.callback.map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                        appst.callback = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
             0 => {
                 if self.app_state.is_none() {
                     self.app_state.put(AppState {
-                        callback: Some(callback),
+                        callback: callback,
                         write_buffer: None,
                         read_buffer: None,
                     });
                 } else {
-                    self.app_state.map(|appst| appst.callback = Some(callback));
+                    self.app_state.map(|appst| appst.callback = callback);
                 }
                 ReturnCode::SUCCESS
             }
@@ -6,6 +6,7 @@
 
 use core::cell::Cell;
 use kernel::{AppId, Callback, Driver, ReturnCode};
+use kernel::container::ResetClient;
 use kernel::hil::gpio::{Pin, PinCtl, InputMode, InterruptMode, Client};
 
 pub struct GPIO<'a, G: Pin + 'a> {
@@ -80,13 +81,32 @@ impl<'a, G: Pin> Client for GPIO<'a, G> {
     }
 }
 
+impl<'a, G: Pin + PinCtl> ResetClient for GPIO<'a, G> {
+    fn containers_reset(&self, appid: AppId) {
+        if self.callback.get().map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+            self.callback.set(None);
+        }
+    }
+}
+
 impl<'a, G: Pin + PinCtl> Driver for GPIO<'a, G> {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                if self.callback.get().map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                    self.callback.set(None);
+                }
+                ReturnCode::SUCCESS
+            }
+
             // subscribe to all pin interrupts
             // (no affect or reliance on individual pins being configured as interrupts)
             0 => {
-                self.callback.set(Some(callback));
+                self.callback.set(callback);
                 ReturnCode::SUCCESS
             }
 
@@ -15,6 +15,7 @@ use core::cmp;
 use kernel::{AppId, AppSlice, Callback, Driver, Shared};
 
 use kernel::common::take_cell::{TakeCell, MapCell};
+use kernel::container::ResetClient;
 use kernel::hil;
 use kernel::returncode::ReturnCode;
 
@@ -74,6 +75,21 @@ impl<'a> I2CMasterSlaveDriver<'a> {
     }
 }
 
+/// Stores a buffer passed with `allow`, or drops the stored buffer if `appid`
+/// is revoking it.
+fn allow_buffer(buffer: &mut Option<AppSlice<Shared, u8>>,
+                slice: Option<AppSlice<Shared, u8>>,
+                appid: AppId) {
+    match slice {
+        Some(_) => *buffer = slice,
+        None => {
+            if buffer.as_ref().map_or(false, |b| b.app_id().idx() == appid.idx()) {
+                *buffer = None;
+            }
+        }
+    }
+}
+
 impl<'a> hil::i2c::I2CHwMasterClient for I2CMasterSlaveDriver<'a> {
     fn command_complete(&self, buffer: &'static mut [u8], error: hil::i2c::Error) {
 
@@ -191,38 +207,76 @@ impl<'a> hil::i2c::I2CHwSlaveClient for I2CMasterSlaveDriver<'a> {
 }
 
 
+impl<'a> ResetClient for I2CMasterSlaveDriver<'a> {
+    fn containers_reset(&self, appid: AppId) {
+        self.app_state.map(|app_state| {
+            if app_state.callback.map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+                app_state.callback = None;
+            }
+            allow_buffer(&mut app_state.master_tx_buffer, None, appid);
+            allow_buffer(&mut app_state.master_rx_buffer, None, appid);
+            allow_buffer(&mut app_state.slave_tx_buffer, None, appid);
+            allow_buffer(&mut app_state.slave_rx_buffer, None, appid);
+        });
+    }
+}
+
 impl<'a> Driver for I2CMasterSlaveDriver<'a> {
-    fn allow(&self, _appid: AppId, allow_num: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         match allow_num {
             // Pass in a buffer for transmitting a `write` to another
             // I2C device.
             0 => {
-                self.app_state.map(|app_state| { app_state.master_tx_buffer = Some(slice); });
+                self.app_state.map(|app_state| {
+                    allow_buffer(&mut app_state.master_tx_buffer, slice, appid)
+                });
                 ReturnCode::SUCCESS
             }
             // Pass in a buffer for doing a read from another I2C device.
             1 => {
-                self.app_state.map(|app_state| { app_state.master_rx_buffer = Some(slice); });
+                self.app_state.map(|app_state| {
+                    allow_buffer(&mut app_state.master_rx_buffer, slice, appid)
+                });
                 ReturnCode::SUCCESS
             }
             // Pass in a buffer for handling a read issued by another I2C master.
             2 => {
-                self.app_state.map(|app_state| { app_state.slave_tx_buffer = Some(slice); });
+                self.app_state.map(|app_state| {
+                    allow_buffer(&mut app_state.slave_tx_buffer, slice, appid)
+                });
                 ReturnCode::SUCCESS
             }
             // Pass in a buffer for handling a write issued by another I2C master.
             3 => {
-                self.app_state.map(|app_state| { app_state.slave_rx_buffer = Some(slice); });
+                self.app_state.map(|app_state| {
+                    allow_buffer(&mut app_state.slave_rx_buffer, slice, appid)
+                });
                 ReturnCode::SUCCESS
             }
             _ => ReturnCode::ENOSUPPORT,
         }
     }
 
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                self.app_state.map(|app_state| {
+                    if app_state.callback.map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                        app_state.callback = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
             0 => {
-                self.app_state.map(|app_state| { app_state.callback = Some(callback); });
+                self.app_state.map(|app_state| { app_state.callback = callback; });
                 ReturnCode::SUCCESS
             }
 
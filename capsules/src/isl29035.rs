@@ -2,6 +2,7 @@
 
 use core::cell::Cell;
 use kernel::{AppId, Callback, Driver, ReturnCode};
+use kernel::container::ResetClient;
 use kernel::common::take_cell::TakeCell;
 use kernel::hil::i2c::{I2CDevice, I2CClient, Error};
 use kernel::hil::time::{self, Frequency};
@@ -60,11 +61,29 @@ impl<'a, A: time::Alarm + 'a> Isl29035<'a, A> {
     }
 }
 
+impl<'a, A: time::Alarm + 'a> ResetClient for Isl29035<'a, A> {
+    fn containers_reset(&self, appid: AppId) {
+        if self.callback.get().map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+            self.callback.set(None);
+        }
+    }
+}
+
 impl<'a, A: time::Alarm + 'a> Driver for Isl29035<'a, A> {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                if self.callback.get().map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                    self.callback.set(None);
+                }
+                ReturnCode::SUCCESS
+            }
             0 => {
-                self.callback.set(Some(callback));
+                self.callback.set(callback);
                 ReturnCode::SUCCESS
             }
             _ => ReturnCode::ENOSUPPORT,
@@ -26,3 +26,8 @@ pub mod rf233;
 pub mod rf233_const;
 pub mod radio;
 pub mod rng;
+pub mod process_stats;
+pub mod process_control;
+pub mod app_loader;
+
+mod app_buffer;
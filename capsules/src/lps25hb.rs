@@ -4,6 +4,7 @@
 
 use core::cell::Cell;
 use kernel::{AppId, Callback, Driver, ReturnCode};
+use kernel::container::ResetClient;
 
 use kernel::common::take_cell::TakeCell;
 use kernel::hil::gpio;
@@ -197,13 +198,32 @@ impl<'a> gpio::Client for LPS25HB<'a> {
     }
 }
 
+impl<'a> ResetClient for LPS25HB<'a> {
+    fn containers_reset(&self, appid: AppId) {
+        if self.callback.get().map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+            self.callback.set(None);
+        }
+    }
+}
+
 impl<'a> Driver for LPS25HB<'a> {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                if self.callback.get().map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                    self.callback.set(None);
+                }
+                ReturnCode::SUCCESS
+            }
+
             // Set a callback
             0 => {
                 // Set callback function
-                self.callback.set(Some(callback));
+                self.callback.set(callback);
                 ReturnCode::SUCCESS
             }
             // default
@@ -6,6 +6,7 @@
 
 use kernel::{AppId, Callback, AppSlice, Driver, ReturnCode, Shared};
 use kernel::common::take_cell::{MapCell, TakeCell};
+use kernel::container::ResetClient;
 use kernel::hil::uart::{self, UARTAdvanced, Client};
 
 struct App {
@@ -53,15 +54,52 @@ impl<'a, U: UARTAdvanced> Nrf51822Serialization<'a, U> {
     }
 }
 
+impl<'a, U: UARTAdvanced> ResetClient for Nrf51822Serialization<'a, U> {
+    fn containers_reset(&self, appid: AppId) {
+        self.app.map(|app| {
+            let owned = |slice: &Option<AppSlice<Shared, u8>>| {
+                slice.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx())
+            };
+            if app.callback.map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+                app.callback = None;
+            }
+            if owned(&app.rx_buffer) {
+                app.rx_buffer = None;
+                app.rx_recv_so_far = 0;
+                app.rx_recv_total = 0;
+            }
+            if owned(&app.tx_buffer) {
+                app.tx_buffer = None;
+            }
+        });
+    }
+}
+
 impl<'a, U: UARTAdvanced> Driver for Nrf51822Serialization<'a, U> {
     /// Pass application space memory to this driver.
-    fn allow(&self, _appid: AppId, allow_type: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_type: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         match allow_type {
+            // Withdraw the RX buffer.
+            0 if slice.is_none() => {
+                self.app.map(|app| {
+                    if app.rx_buffer.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx()) {
+                        app.rx_buffer = None;
+                        app.rx_recv_so_far = 0;
+                        app.rx_recv_total = 0;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
+
             // Provide an RX buffer.
             0 => {
                 let resapp = match self.app.take() {
                     Some(mut app) => {
-                        app.rx_buffer = Some(slice);
+                        app.rx_buffer = slice;
                         app.rx_recv_so_far = 0;
                         app.rx_recv_total = 0;
                         app
@@ -70,7 +108,7 @@ impl<'a, U: UARTAdvanced> Driver for Nrf51822Serialization<'a, U> {
                         App {
                             callback: None,
                             tx_buffer: None,
-                            rx_buffer: Some(slice),
+                            rx_buffer: slice,
                             rx_recv_so_far: 0,
                             rx_recv_total: 0,
                         }
@@ -80,17 +118,27 @@ impl<'a, U: UARTAdvanced> Driver for Nrf51822Serialization<'a, U> {
                 ReturnCode::SUCCESS
             }
 
+            // Withdraw the TX buffer.
+            1 if slice.is_none() => {
+                self.app.map(|app| {
+                    if app.tx_buffer.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx()) {
+                        app.tx_buffer = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
+
             // Provide a TX buffer.
             1 => {
                 let resapp = match self.app.take() {
                     Some(mut app) => {
-                        app.tx_buffer = Some(slice);
+                        app.tx_buffer = slice;
                         app
                     }
                     None => {
                         App {
                             callback: None,
-                            tx_buffer: Some(slice),
+                            tx_buffer: slice,
                             rx_buffer: None,
                             rx_recv_so_far: 0,
                             rx_recv_total: 0,
@@ -109,13 +157,27 @@ impl<'a, U: UARTAdvanced> Driver for Nrf51822Serialization<'a, U> {
     /// The callback will be called when a TX finishes and when
     /// RX data is available.
     #[inline(never)]
-    fn subscribe(&self, subscribe_type: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_type: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_type {
+            // Remove the callback
+            0 if callback.is_none() => {
+                self.app.map(|app| {
+                    if app.callback.map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                        app.callback = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
+
             // Add a callback
             0 => {
                 let resapp = match self.app.take() {
                     Some(mut app) => {
-                        app.callback = Some(callback);
+                        app.callback = callback;
                         app
                     }
                     None => {
@@ -126,7 +188,7 @@ impl<'a, U: UARTAdvanced> Driver for Nrf51822Serialization<'a, U> {
                             .map(|buffer| { self.uart.receive_automatic(buffer, 250); });
 
                         App {
-                            callback: Some(callback),
+                            callback: callback,
                             tx_buffer: None,
                             rx_buffer: None,
                             rx_recv_so_far: 0,
@@ -159,7 +221,7 @@ impl<'a, U: UARTAdvanced> Driver for Nrf51822Serialization<'a, U> {
                 self.app.map_or(ReturnCode::FAIL, |appst| {
 
                     match appst.tx_buffer.take() {
-                        Some(slice) => {
+                        slice => {
                             let write_len = slice.len();
                             self.tx_buffer.take().map(|buffer| {
                                 for (i, c) in slice.as_ref().iter().enumerate() {
@@ -0,0 +1,144 @@
+//! Process Control Capsule
+//!
+//! Lets a single controlling application, identified by its package name in
+//! a signed image, list the processes on the board and stop, resume or
+//! restart them. All other applications see the driver as unsupported, as do
+//! all applications on boards that do not require apps to be signed.
+//!
+//! Processes are identified by their slot index. Command 1 returns the
+//! number of slots. For information about a process, the controller allows a
+//! buffer (allow 0) and issues command 2 with the process index. The buffer
+//! is filled with little-endian 32-bit words in this order:
+//!
+//!   0. state (0 = running, 1 = yielded, 2 = fault, 3 = stopped)
+//!   1. syscall count
+//!   2. size of the process's memory in bytes
+//!   3. bytes of memory used by the app (data, stack and heap)
+//!   4. bytes of memory used by the kernel for grants
+//!   5. length of the package name
+//!
+//! followed by as much of the package name as fits in the buffer.
+//!
+//! Commands 3, 4 and 5 stop, resume and restart the process with the given
+//! index. The controller cannot use them on itself.
+
+use app_buffer;
+use kernel::{AppId, AppSlice, Container, Driver, ReturnCode, Shared};
+use kernel::process;
+
+/// Number of bytes written by the information command before the package
+/// name.
+pub const INFO_HEADER_LEN: usize = 6 * 4;
+
+pub struct App {
+    buffer: Option<AppSlice<Shared, u8>>,
+}
+
+impl Default for App {
+    fn default() -> App {
+        App { buffer: None }
+    }
+}
+
+pub struct ProcessControl {
+    apps: Container<App>,
+    controller_name: &'static str,
+}
+
+impl ProcessControl {
+    pub fn new(container: Container<App>, controller_name: &'static str) -> ProcessControl {
+        ProcessControl {
+            apps: container,
+            controller_name: controller_name,
+        }
+    }
+
+    fn is_controller(&self, appid: AppId) -> bool {
+        process::is_trusted_app(appid, self.controller_name)
+    }
+
+    fn info(&self, appid: AppId, index: usize) -> ReturnCode {
+        let (words, name) = match process::with_process(index, |p| {
+            let stats = p.statistics();
+            let mem_start = p.mem_start() as usize;
+            ([usize::from(stats.state),
+              stats.syscall_count,
+              p.mem_end() as usize - mem_start,
+              p.app_memory_break() as usize - mem_start,
+              stats.grant_bytes_used,
+              p.package_name.len()],
+             p.package_name)
+        }) {
+            Some(info) => info,
+            None => return ReturnCode::EINVAL,
+        };
+
+        self.apps
+            .enter(appid, |app, _| {
+                app_buffer::write_words(&mut app.buffer, &words, name.as_bytes())
+            })
+            .unwrap_or_else(ReturnCode::from)
+    }
+}
+
+impl Driver for ProcessControl {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
+        if !self.is_controller(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match allow_num {
+            0 => {
+                self.apps
+                    .enter(appid, |app, _| {
+                        app.buffer = slice;
+                        ReturnCode::SUCCESS
+                    })
+                    .unwrap_or_else(ReturnCode::from)
+            }
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+
+    fn command(&self, command_num: usize, data: usize, appid: AppId) -> ReturnCode {
+        if !self.is_controller(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match command_num {
+            0 /* check if present */ => ReturnCode::SUCCESS,
+            1 /* number of process slots */ => {
+                ReturnCode::SuccessWithValue { value: process::num_processes() }
+            }
+            2 /* information about process `data` */ => self.info(appid, data),
+            3 | 4 | 5 if data == appid.idx() => ReturnCode::EINVAL,
+            3 /* stop process `data` */ => {
+                let stopped = unsafe { process::with_process_mut(data, |p| p.stop()) };
+                match stopped {
+                    Some(true) => ReturnCode::SUCCESS,
+                    Some(false) => ReturnCode::EALREADY,
+                    None => ReturnCode::EINVAL,
+                }
+            }
+            4 /* resume process `data` */ => {
+                let resumed = unsafe { process::with_process_mut(data, |p| p.resume()) };
+                match resumed {
+                    Some(true) => ReturnCode::SUCCESS,
+                    Some(false) => ReturnCode::EALREADY,
+                    None => ReturnCode::EINVAL,
+                }
+            }
+            5 /* restart process `data` */ => {
+                let restarted = unsafe { process::with_process_mut(data, |p| p.restart()) };
+                match restarted {
+                    Some(true) => ReturnCode::SUCCESS,
+                    Some(false) => ReturnCode::FAIL,
+                    None => ReturnCode::EINVAL,
+                }
+            }
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Process Statistics Capsule
+//!
+//! Exposes per-process accounting (CPU time, syscalls, callbacks and grant
+//! usage) to a single monitoring application, identified by its package
+//! name in a signed image. All other applications see the driver as
+//! unsupported, as do all applications on boards that do not require apps to
+//! be signed.
+//!
+//! The monitoring app allows a buffer (allow 0) and then asks for the
+//! statistics of a process by index (command 2). The buffer is filled with
+//! little-endian 32-bit words in this order:
+//!
+//!   0. state (0 = running, 1 = yielded, 2 = fault, 3 = stopped)
+//!   1. syscall count
+//!   2. CPU time in microseconds
+//!   3. timeslice expirations
+//!   4. callbacks delivered
+//!   5. callbacks dropped
+//!   6. grant bytes used
+//!   7. restart count
+//!   8. grant bytes allocated by containers
+//!   9. grant limit from the app header (0 = no limit)
+//!  10. failed grant allocations
+
+use app_buffer;
+use kernel::{AppId, AppSlice, Container, Driver, ReturnCode, Shared};
+use kernel::process;
+
+/// Number of bytes written by the statistics command.
+pub const STATISTICS_LEN: usize = 11 * 4;
+
+pub struct App {
+    buffer: Option<AppSlice<Shared, u8>>,
+}
+
+impl Default for App {
+    fn default() -> App {
+        App { buffer: None }
+    }
+}
+
+pub struct ProcessStats {
+    apps: Container<App>,
+    monitor_name: &'static str,
+}
+
+impl ProcessStats {
+    pub fn new(container: Container<App>, monitor_name: &'static str) -> ProcessStats {
+        ProcessStats {
+            apps: container,
+            monitor_name: monitor_name,
+        }
+    }
+
+    fn is_monitor(&self, appid: AppId) -> bool {
+        process::is_trusted_app(appid, self.monitor_name)
+    }
+}
+
+impl Driver for ProcessStats {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
+        if !self.is_monitor(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match allow_num {
+            0 => {
+                self.apps
+                    .enter(appid, |app, _| {
+                        app.buffer = slice;
+                        ReturnCode::SUCCESS
+                    })
+                    .unwrap_or_else(ReturnCode::from)
+            }
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+
+    fn command(&self, command_num: usize, data: usize, appid: AppId) -> ReturnCode {
+        if !self.is_monitor(appid) {
+            return ReturnCode::ENOSUPPORT;
+        }
+        match command_num {
+            0 /* check if present */ => ReturnCode::SUCCESS,
+            1 /* number of process slots */ => {
+                ReturnCode::SuccessWithValue { value: process::num_processes() }
+            }
+            2 /* statistics of process `data` */ => {
+                let stats = match process::with_process(data, |p| p.statistics()) {
+                    Some(stats) => stats,
+                    None => return ReturnCode::EINVAL,
+                };
+                let words = [usize::from(stats.state),
+                             stats.syscall_count,
+                             stats.cpu_time_us,
+                             stats.timeslice_expirations,
+                             stats.callbacks_delivered,
+                             stats.callbacks_dropped,
+                             stats.grant_bytes_used,
+                             stats.restart_count,
+                             stats.grant_bytes_allocated,
+                             stats.grant_limit.unwrap_or(0),
+                             stats.grant_alloc_failures];
+
+                self.apps
+                    .enter(appid, |app, _| app_buffer::write_words(&mut app.buffer, &words, &[]))
+                    .unwrap_or_else(ReturnCode::from)
+            }
+            _ => ReturnCode::ENOSUPPORT,
+        }
+    }
+}
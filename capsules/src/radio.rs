@@ -10,6 +10,7 @@
 use core::cell::Cell;
 use kernel::{AppId, Driver, Callback, AppSlice, Shared};
 use kernel::common::take_cell::{MapCell, TakeCell};
+use kernel::container::ResetClient;
 use kernel::hil::radio;
 use kernel::returncode::ReturnCode;
 
@@ -42,21 +43,63 @@ impl<'a, R: radio::Radio> RadioDriver<'a, R> {
     }
 }
 
+impl<'a, R: radio::Radio> ResetClient for RadioDriver<'a, R> {
+    fn containers_reset(&self, appid: AppId) {
+        self.app.map(|app| {
+            let owned = |slice: &Option<AppSlice<Shared, u8>>| {
+                slice.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx())
+            };
+            if app.tx_callback.map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+                app.tx_callback = None;
+            }
+            if app.rx_callback.map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+                app.rx_callback = None;
+            }
+            if owned(&app.app_read) {
+                app.app_read = None;
+            }
+            if owned(&app.app_write) {
+                app.app_write = None;
+            }
+        });
+    }
+}
+
 impl<'a, R: radio::Radio> Driver for RadioDriver<'a, R> {
-    fn allow(&self, _appid: AppId, allow_num: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         match allow_num {
+            0 if slice.is_none() => {
+                self.app.map(|appc| {
+                    if appc.app_read.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx()) {
+                        appc.app_read = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
+            1 if slice.is_none() => {
+                self.app.map(|appc| {
+                    if appc.app_write.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx()) {
+                        appc.app_write = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
             0 => {
                 let appc = match self.app.take() {
                     None => {
                         App {
                             tx_callback: None,
                             rx_callback: None,
-                            app_read: Some(slice),
+                            app_read: slice,
                             app_write: None,
                         }
                     }
                     Some(mut appc) => {
-                        appc.app_read = Some(slice);
+                        appc.app_read = slice;
                         appc
                     }
                 };
@@ -70,11 +113,11 @@ impl<'a, R: radio::Radio> Driver for RadioDriver<'a, R> {
                             tx_callback: None,
                             rx_callback: None,
                             app_read: None,
-                            app_write: Some(slice),
+                            app_write: slice,
                         }
                     }
                     Some(mut appc) => {
-                        appc.app_write = Some(slice);
+                        appc.app_write = slice;
                         appc
                     }
                 };
@@ -85,18 +128,38 @@ impl<'a, R: radio::Radio> Driver for RadioDriver<'a, R> {
         }
     }
 
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                self.app.map(|appc| {
+                    if appc.tx_callback.map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                        appc.tx_callback = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            },
+            1 if callback.is_none() => {
+                self.app.map(|appc| {
+                    if appc.rx_callback.map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                        appc.rx_callback = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            },
             0 /* transmit done*/  => {
                 let appc = match self.app.take() {
                     None => App {
-                        tx_callback: Some(callback),
+                        tx_callback: callback,
                         rx_callback: None,
                         app_read: None,
                         app_write: None,
                     },
                     Some(mut appc) => {
-                        appc.tx_callback = Some(callback);
+                        appc.tx_callback = callback;
                         appc
                     }
                 };
@@ -107,12 +170,12 @@ impl<'a, R: radio::Radio> Driver for RadioDriver<'a, R> {
                 let appc = match self.app.take() {
                     None => App {
                         tx_callback: None,
-                        rx_callback: Some(callback),
+                        rx_callback: callback,
                         app_read: None,
                         app_write: None,
                     },
                     Some(mut appc) => {
-                        appc.rx_callback = Some(callback);
+                        appc.rx_callback = callback;
                         appc
                     }
                 };
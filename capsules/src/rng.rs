@@ -120,13 +120,17 @@ impl<'a, RNG: rng::RNG> rng::Client for SimpleRng<'a, RNG> {
 }
 
 impl<'a, RNG: rng::RNG> Driver for SimpleRng<'a, RNG> {
-    fn allow(&self, appid: AppId, allow_num: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         // pass buffer in from application
         match allow_num {
             0 => {
                 self.apps
                     .enter(appid, |app, _| {
-                        app.buffer = Some(slice);
+                        app.buffer = slice;
                         ReturnCode::SUCCESS
                     })
                     .unwrap_or_else(|err| match err {
@@ -139,12 +143,16 @@ impl<'a, RNG: rng::RNG> Driver for SimpleRng<'a, RNG> {
         }
     }
 
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
             0 => {
                 self.apps
-                    .enter(callback.app_id(), |app, _| {
-                        app.callback = Some(callback);
+                    .enter(app_id, |app, _| {
+                        app.callback = callback;
                         ReturnCode::SUCCESS
                     })
                     .unwrap_or_else(|err| match err {
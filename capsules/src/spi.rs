@@ -5,6 +5,7 @@ use core::cell::Cell;
 use core::cmp;
 use kernel::{AppId, AppSlice, Callback, Driver, ReturnCode, Shared};
 use kernel::common::take_cell::{MapCell, TakeCell};
+use kernel::container::ResetClient;
 use kernel::hil::spi::{SpiMasterDevice, SpiMasterClient};
 use kernel::hil::spi::ClockPhase;
 use kernel::hil::spi::ClockPolarity;
@@ -75,22 +76,63 @@ impl<'a, S: SpiMasterDevice> Spi<'a, S> {
     }
 }
 
+impl<'a, S: SpiMasterDevice> ResetClient for Spi<'a, S> {
+    fn containers_reset(&self, appid: AppId) {
+        self.app.map(|app| {
+            let owned = |slice: &Option<AppSlice<Shared, u8>>| {
+                slice.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx())
+            };
+            if app.callback.map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+                app.callback = None;
+            }
+            if owned(&app.app_read) {
+                app.app_read = None;
+            }
+            if owned(&app.app_write) {
+                app.app_write = None;
+                // A transfer out of the buffer ends with the chunk in flight
+                app.len = app.index;
+            }
+        });
+    }
+}
+
 impl<'a, S: SpiMasterDevice> Driver for Spi<'a, S> {
-    fn allow(&self, _appid: AppId, allow_num: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             allow_num: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         match allow_num {
+            0 if slice.is_none() => {
+                self.app.map(|appc| {
+                    if appc.app_read.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx()) {
+                        appc.app_read = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
+            1 if slice.is_none() => {
+                self.app.map(|appc| {
+                    if appc.app_write.as_ref().map_or(false, |s| s.app_id().idx() == appid.idx()) {
+                        appc.app_write = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            }
             0 => {
                 let appc = match self.app.take() {
                     None => {
                         App {
                             callback: None,
-                            app_read: Some(slice),
+                            app_read: slice,
                             app_write: None,
                             len: 0,
                             index: 0,
                         }
                     }
                     Some(mut appc) => {
-                        appc.app_read = Some(slice);
+                        appc.app_read = slice;
                         appc
                     }
                 };
@@ -103,13 +145,13 @@ impl<'a, S: SpiMasterDevice> Driver for Spi<'a, S> {
                         App {
                             callback: None,
                             app_read: None,
-                            app_write: Some(slice),
+                            app_write: slice,
                             len: 0,
                             index: 0,
                         }
                     }
                     Some(mut appc) => {
-                        appc.app_write = Some(slice);
+                        appc.app_write = slice;
                         appc
                     }
                 };
@@ -121,19 +163,31 @@ impl<'a, S: SpiMasterDevice> Driver for Spi<'a, S> {
     }
 
     #[inline(never)]
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            0 if callback.is_none() => {
+                self.app.map(|appc| {
+                    if appc.callback.map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                        appc.callback = None;
+                    }
+                });
+                ReturnCode::SUCCESS
+            },
             0 /* read_write */ => {
                 let appc = match self.app.take() {
                     None => App {
-                        callback: Some(callback),
+                        callback: callback,
                         app_read: None,
                         app_write: None,
                         len: 0,
                         index: 0,
                     },
                     Some(mut appc) => {
-                        appc.callback = Some(callback);
+                        appc.callback = callback;
                         appc
                     }
                 };
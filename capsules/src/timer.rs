@@ -4,6 +4,7 @@
 
 use core::cell::Cell;
 use kernel::{AppId, Callback, Container, Driver, ReturnCode};
+use kernel::container::ResetClient;
 use kernel::hil::time::{self, Alarm, Frequency};
 use kernel::process::Error;
 
@@ -62,10 +63,10 @@ impl<'a, A: Alarm> TimerDriver<'a, A> {
 }
 
 impl<'a, A: Alarm> Driver for TimerDriver<'a, A> {
-    fn subscribe(&self, _: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self, _: usize, callback: Option<Callback>, app_id: AppId) -> ReturnCode {
         self.app_timer
-            .enter(callback.app_id(), |td, _allocator| {
-                td.callback = Some(callback);
+            .enter(app_id, |td, _allocator| {
+                td.callback = callback;
                 ReturnCode::SUCCESS
             })
             .unwrap_or_else(|err| match err {
@@ -90,8 +91,13 @@ impl<'a, A: Alarm> Driver for TimerDriver<'a, A> {
                 match cmd_type {
                 0 /* check if present */ => (ReturnCode::SUCCESS, false),
                 4 /* capture time */ => {
+                    // The full 32-bit time doesn't fit in a positive return
+                    // code, so return it along with the clock frequency
                     let curr_time: u32 = self.alarm.now();
-                    (ReturnCode::SuccessWithValue { value: curr_time as usize }, true)
+                    let frequency = <A::Frequency>::frequency();
+                    (ReturnCode::SuccessWithValues {
+                        values: [curr_time as usize, frequency as usize, 0],
+                    }, true)
                 },
                 3 /* Stop */ => {
                     if td.interval > 0 {
@@ -188,3 +194,18 @@ impl<'a, A: Alarm> time::Client for TimerDriver<'a, A> {
         }
     }
 }
+
+impl<'a, A: Alarm> ResetClient for TimerDriver<'a, A> {
+    fn containers_reset(&self, appid: AppId) {
+        // A timer the app had armed no longer counts
+        self.app_timer.container(appid).map(|timer| {
+            timer.enter(|timer, _| if timer.interval > 0 {
+                timer.interval = 0;
+                self.num_armed.set(self.num_armed.get() - 1);
+            });
+        });
+        if self.num_armed.get() == 0 {
+            self.alarm.disable();
+        }
+    }
+}
@@ -4,6 +4,7 @@
 
 use core::cell::Cell;
 use kernel::{AppId, Callback, Driver, ReturnCode};
+use kernel::container::ResetClient;
 use kernel::common::math::{sqrtf32, get_errno};
 use kernel::common::take_cell::TakeCell;
 use kernel::hil::gpio::{Pin, InterruptMode, Client};
@@ -257,16 +258,40 @@ impl<'a> Client for TMP006<'a> {
     }
 }
 
+impl<'a> ResetClient for TMP006<'a> {
+    fn containers_reset(&self, appid: AppId) {
+        if self.callback.get().map_or(false, |cb| cb.app_id().idx() == appid.idx()) {
+            self.callback.set(None);
+            self.repeated_mode.set(false);
+            self.disable_sensor(None);
+        }
+    }
+}
+
 impl<'a> Driver for TMP006<'a> {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
+            // stop readings for a revoked callback
+            0 | 1 if callback.is_none() => {
+                if self.callback.get().map_or(false, |cb| cb.app_id().idx() == app_id.idx()) {
+                    self.callback.set(None);
+                    self.repeated_mode.set(false);
+                    self.disable_sensor(None);
+                }
+                ReturnCode::SUCCESS
+            }
+
             // single temperature reading with callback
             0 => {
                 // single sample mode
                 self.repeated_mode.set(false);
 
                 // set callback function
-                self.callback.set(Some(callback));
+                self.callback.set(callback);
 
                 // enable sensor
                 //  turn up the sampling rate so we get the sample faster
@@ -281,7 +306,7 @@ impl<'a> Driver for TMP006<'a> {
                 self.repeated_mode.set(true);
 
                 // set callback function
-                self.callback.set(Some(callback));
+                self.callback.set(callback);
 
                 // enable temperature sensor
                 self.enable_sensor(self.sampling_period.get());
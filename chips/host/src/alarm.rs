@@ -0,0 +1,94 @@
+//! A 32 kHz alarm driven by the host's monotonic clock.
+
+use kernel::hil::time::{self, Alarm as AlarmTrait, Freq32KHz, Frequency, Time};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+pub struct Alarm {
+    epoch: Instant,
+    alarm: Cell<u32>,
+    /// The counter value the alarm interval is measured from.
+    armed_at: Cell<u32>,
+    armed: Cell<bool>,
+    client: Cell<Option<&'static time::Client>>,
+}
+
+impl Alarm {
+    pub fn new() -> Alarm {
+        Alarm {
+            epoch: Instant::now(),
+            alarm: Cell::new(0),
+            armed_at: Cell::new(0),
+            armed: Cell::new(false),
+            client: Cell::new(None),
+        }
+    }
+
+    pub fn set_client(&self, client: &'static time::Client) {
+        self.client.set(Some(client));
+    }
+
+    fn remaining(&self) -> u32 {
+        let interval = self.alarm.get().wrapping_sub(self.armed_at.get());
+        let elapsed = self.now().wrapping_sub(self.armed_at.get());
+        interval.saturating_sub(elapsed)
+    }
+
+    pub fn pending(&self) -> bool {
+        self.armed.get() && self.remaining() == 0
+    }
+
+    /// How long until the alarm fires, if it is armed.
+    pub fn time_until_fire(&self) -> Option<Duration> {
+        if !self.armed.get() {
+            return None;
+        }
+        let nanos = self.remaining() as u64 * 1_000_000_000 / Freq32KHz::frequency() as u64;
+        Some(Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32))
+    }
+
+    pub fn handle_interrupt(&self) {
+        self.armed.set(false);
+        self.client.get().map(|client| client.fired());
+    }
+}
+
+impl Time for Alarm {
+    fn disable(&self) {
+        self.armed.set(false);
+    }
+
+    fn is_armed(&self) -> bool {
+        self.armed.get()
+    }
+}
+
+impl AlarmTrait for Alarm {
+    type Frequency = Freq32KHz;
+
+    fn now(&self) -> u32 {
+        let elapsed = self.epoch.elapsed();
+        let frequency = Freq32KHz::frequency() as u64;
+        let tics = elapsed.as_secs() * frequency +
+                   elapsed.subsec_nanos() as u64 * frequency / 1_000_000_000;
+        tics as u32
+    }
+
+    fn set_alarm(&self, tics: u32) {
+        // The host may have been descheduled for a while, so an alarm set
+        // for a time that has just passed fires immediately instead of after
+        // the counter wraps.
+        let now = self.now();
+        if tics.wrapping_sub(now) > u32::max_value() / 2 {
+            self.armed_at.set(tics);
+        } else {
+            self.armed_at.set(now);
+        }
+        self.alarm.set(tics);
+        self.armed.set(true);
+    }
+
+    fn get_alarm(&self) -> u32 {
+        self.alarm.get()
+    }
+}
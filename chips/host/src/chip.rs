@@ -0,0 +1,121 @@
+use alarm::Alarm;
+use gpio::Pin;
+use i2c::I2C;
+use kernel::Chip;
+use leak;
+use spi::Spi;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use uart::Uart;
+
+/// Number of simulated GPIO pins.
+pub const NUM_PINS: usize = 16;
+
+/// Longest time `sleep` waits before checking for work again.
+const MAX_SLEEP_MS: u64 = 100;
+
+/// Wakes the kernel from `sleep` when a thread standing in for hardware
+/// (such as the stdin reader) has new input.
+pub struct Wakeup {
+    pending: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Wakeup {
+    pub fn new() -> Wakeup {
+        Wakeup {
+            pending: Mutex::new(false),
+            condvar: Condvar::new(),
+        }
+    }
+
+    pub fn notify(&self) {
+        *self.pending.lock().unwrap() = true;
+        self.condvar.notify_one();
+    }
+
+    /// Waits for a notification or until `timeout` has passed.
+    pub fn wait(&self, timeout: Duration) {
+        let mut pending = self.pending.lock().unwrap();
+        if !*pending {
+            pending = self.condvar.wait_timeout(pending, timeout).unwrap().0;
+        }
+        *pending = false;
+    }
+}
+
+pub struct Host {
+    pub uart: &'static Uart,
+    pub alarm: &'static Alarm,
+    pub pins: &'static [Pin],
+    pub i2c: &'static I2C,
+    pub spi: &'static Spi,
+    mpu: (),
+    systick: (),
+    wakeup: Arc<Wakeup>,
+}
+
+impl Host {
+    pub fn new() -> Host {
+        let wakeup = Arc::new(Wakeup::new());
+        let pins: Vec<Pin> = (0..NUM_PINS).map(Pin::new).collect();
+        Host {
+            uart: leak(Uart::new(wakeup.clone())),
+            alarm: leak(Alarm::new()),
+            pins: leak(pins.into_boxed_slice()),
+            i2c: leak(I2C::new()),
+            spi: leak(Spi::new()),
+            mpu: (),
+            systick: (),
+            wakeup: wakeup,
+        }
+    }
+}
+
+impl Chip for Host {
+    type MPU = ();
+    type SysTick = ();
+
+    fn service_pending_interrupts(&mut self) {
+        // Handlers may start new operations that complete immediately, so
+        // keep going until every peripheral is idle.
+        while self.has_pending_interrupts() {
+            if self.alarm.pending() {
+                self.alarm.handle_interrupt();
+            }
+            if self.uart.pending() {
+                self.uart.handle_interrupt();
+            }
+            if self.i2c.pending() {
+                self.i2c.handle_interrupt();
+            }
+            if self.spi.pending() {
+                self.spi.handle_interrupt();
+            }
+            for pin in self.pins.iter() {
+                if pin.pending() {
+                    pin.handle_interrupt();
+                }
+            }
+        }
+    }
+
+    fn has_pending_interrupts(&self) -> bool {
+        self.alarm.pending() || self.uart.pending() || self.i2c.pending() ||
+        self.spi.pending() || self.pins.iter().any(|pin| pin.pending())
+    }
+
+    fn mpu(&self) -> &() {
+        &self.mpu
+    }
+
+    fn systick(&self) -> &() {
+        &self.systick
+    }
+
+    fn sleep(&self) {
+        let max = Duration::from_millis(MAX_SLEEP_MS);
+        let timeout = self.alarm.time_until_fire().map_or(max, |t| if t < max { t } else { max });
+        self.wakeup.wait(timeout);
+    }
+}
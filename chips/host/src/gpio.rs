@@ -0,0 +1,138 @@
+//! GPIO pins backed by memory.
+//!
+//! Output changes are logged to stderr, which is how LEDs show up in the
+//! simulation. Tests and boards drive input pins with `set_input_level`,
+//! which raises an interrupt on a matching edge.
+
+use kernel::hil::gpio::{self, InputMode, InterruptMode};
+use std::cell::Cell;
+use std::io::{self, Write};
+
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    Disabled,
+    Input,
+    Output,
+}
+
+pub struct Pin {
+    number: usize,
+    mode: Cell<Mode>,
+    level: Cell<bool>,
+    rising: Cell<bool>,
+    falling: Cell<bool>,
+    pending: Cell<bool>,
+    client_data: Cell<usize>,
+    client: Cell<Option<&'static gpio::Client>>,
+}
+
+impl Pin {
+    pub fn new(number: usize) -> Pin {
+        Pin {
+            number: number,
+            mode: Cell::new(Mode::Disabled),
+            level: Cell::new(false),
+            rising: Cell::new(false),
+            falling: Cell::new(false),
+            pending: Cell::new(false),
+            client_data: Cell::new(0),
+            client: Cell::new(None),
+        }
+    }
+
+    pub fn set_client<C: gpio::Client>(&self, client: &'static C) {
+        self.client.set(Some(client));
+    }
+
+    /// Simulates an external signal on the pin. Has no effect unless the pin
+    /// is an input.
+    pub fn set_input_level(&self, level: bool) {
+        if self.mode.get() != Mode::Input || self.level.get() == level {
+            return;
+        }
+        self.level.set(level);
+        if (level && self.rising.get()) || (!level && self.falling.get()) {
+            self.pending.set(true);
+        }
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending.get()
+    }
+
+    pub fn handle_interrupt(&self) {
+        self.pending.set(false);
+        self.client.get().map(|client| client.fired(self.client_data.get()));
+    }
+
+    fn drive(&self, level: bool) {
+        if self.mode.get() != Mode::Output || self.level.get() == level {
+            return;
+        }
+        self.level.set(level);
+        let _ = writeln!(io::stderr(),
+                         "gpio: pin {} {}",
+                         self.number,
+                         if level { "high" } else { "low" });
+    }
+}
+
+impl gpio::PinCtl for Pin {
+    fn set_input_mode(&self, mode: InputMode) {
+        if self.mode.get() != Mode::Output {
+            match mode {
+                InputMode::PullUp => self.level.set(true),
+                InputMode::PullDown => self.level.set(false),
+                InputMode::PullNone => {}
+            }
+        }
+    }
+}
+
+impl gpio::Pin for Pin {
+    fn make_output(&self) {
+        self.mode.set(Mode::Output);
+    }
+
+    fn make_input(&self) {
+        self.mode.set(Mode::Input);
+    }
+
+    fn disable(&self) {
+        self.mode.set(Mode::Disabled);
+    }
+
+    fn set(&self) {
+        self.drive(true);
+    }
+
+    fn clear(&self) {
+        self.drive(false);
+    }
+
+    fn toggle(&self) {
+        let level = self.level.get();
+        self.drive(!level);
+    }
+
+    fn read(&self) -> bool {
+        self.level.get()
+    }
+
+    fn enable_interrupt(&self, client_data: usize, mode: InterruptMode) {
+        let (rising, falling) = match mode {
+            InterruptMode::RisingEdge => (true, false),
+            InterruptMode::FallingEdge => (false, true),
+            InterruptMode::EitherEdge => (true, true),
+        };
+        self.client_data.set(client_data);
+        self.rising.set(rising);
+        self.falling.set(falling);
+    }
+
+    fn disable_interrupt(&self) {
+        self.rising.set(false);
+        self.falling.set(false);
+        self.pending.set(false);
+    }
+}
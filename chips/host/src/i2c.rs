@@ -0,0 +1,171 @@
+//! An I2C bus with simulated devices attached.
+//!
+//! Boards attach a `Device` implementation at an address with
+//! `add_device`. Transfers to addresses with nothing attached fail with
+//! `AddressNak`, as they would on a real bus.
+
+use kernel::common::take_cell::TakeCell;
+use kernel::hil::i2c::{self, Error};
+use std::cell::Cell;
+
+/// Number of devices that can be attached to the bus.
+const MAX_DEVICES: usize = 8;
+
+/// A device on the simulated bus.
+pub trait Device {
+    /// The master wrote `data`. Returns `false` to NAK it.
+    fn write(&self, data: &[u8]) -> bool;
+
+    /// The master reads `buffer.len()` bytes. Returns `false` to NAK.
+    fn read(&self, buffer: &mut [u8]) -> bool;
+}
+
+pub struct I2C {
+    enabled: Cell<bool>,
+    devices: [Cell<Option<(u8, &'static Device)>>; MAX_DEVICES],
+    buffer: TakeCell<'static, [u8]>,
+    result: Cell<Error>,
+    master_client: Cell<Option<&'static i2c::I2CHwMasterClient>>,
+}
+
+impl I2C {
+    pub fn new() -> I2C {
+        I2C {
+            enabled: Cell::new(false),
+            devices: Default::default(),
+            buffer: TakeCell::empty(),
+            result: Cell::new(Error::CommandComplete),
+            master_client: Cell::new(None),
+        }
+    }
+
+    pub fn set_master_client(&self, client: &'static i2c::I2CHwMasterClient) {
+        self.master_client.set(Some(client));
+    }
+
+    /// Attaches `device` at `addr`. Returns `false` if the bus is full.
+    pub fn add_device(&self, addr: u8, device: &'static Device) -> bool {
+        for slot in self.devices.iter() {
+            if slot.get().is_none() {
+                slot.set(Some((addr, device)));
+                return true;
+            }
+        }
+        false
+    }
+
+    fn device(&self, addr: u8) -> Option<&'static Device> {
+        self.devices
+            .iter()
+            .filter_map(|slot| slot.get())
+            .find(|&(a, _)| a == addr)
+            .map(|(_, device)| device)
+    }
+
+    /// Runs a transfer against the addressed device and holds on to the
+    /// buffer until the completion interrupt.
+    fn transfer(&self, addr: u8, data: &'static mut [u8], write_len: usize, read_len: usize) {
+        let result = if !self.enabled.get() {
+            Error::ArbitrationLost
+        } else {
+            match self.device(addr) {
+                None => Error::AddressNak,
+                Some(device) => {
+                    let write_ok = write_len == 0 || device.write(&data[..write_len]);
+                    if write_ok && (read_len == 0 || device.read(&mut data[..read_len])) {
+                        Error::CommandComplete
+                    } else {
+                        Error::DataNak
+                    }
+                }
+            }
+        };
+        self.result.set(result);
+        self.buffer.replace(data);
+    }
+
+    pub fn pending(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    pub fn handle_interrupt(&self) {
+        self.buffer.take().map(|buffer| {
+            self.master_client.get().map(|client| {
+                client.command_complete(buffer, self.result.get());
+            });
+        });
+    }
+}
+
+impl i2c::I2CMaster for I2C {
+    fn enable(&self) {
+        self.enabled.set(true);
+    }
+
+    fn disable(&self) {
+        self.enabled.set(false);
+    }
+
+    fn write_read(&self, addr: u8, data: &'static mut [u8], write_len: u8, read_len: u8) {
+        self.transfer(addr, data, write_len as usize, read_len as usize);
+    }
+
+    fn write(&self, addr: u8, data: &'static mut [u8], len: u8) {
+        self.transfer(addr, data, len as usize, 0);
+    }
+
+    fn read(&self, addr: u8, buffer: &'static mut [u8], len: u8) {
+        self.transfer(addr, buffer, 0, len as usize);
+    }
+}
+
+/// A device with 256 byte-wide registers, like many sensors and EEPROMs.
+///
+/// The first byte of a write selects the register, and the rest of the write
+/// and any following read access consecutive registers from there.
+pub struct RegisterFile {
+    registers: [Cell<u8>; 256],
+    pointer: Cell<u8>,
+}
+
+impl RegisterFile {
+    pub fn new() -> RegisterFile {
+        let registers: [Cell<u8>; 256] = unsafe { ::std::mem::zeroed() };
+        RegisterFile {
+            registers: registers,
+            pointer: Cell::new(0),
+        }
+    }
+
+    pub fn register(&self, index: u8) -> u8 {
+        self.registers[index as usize].get()
+    }
+
+    pub fn set_register(&self, index: u8, value: u8) {
+        self.registers[index as usize].set(value);
+    }
+
+    fn next(&self) -> u8 {
+        let index = self.pointer.get();
+        self.pointer.set(index.wrapping_add(1));
+        index
+    }
+}
+
+impl Device for RegisterFile {
+    fn write(&self, data: &[u8]) -> bool {
+        data.first().map(|&index| self.pointer.set(index));
+        for &byte in data.iter().skip(1) {
+            let index = self.next();
+            self.set_register(index, byte);
+        }
+        true
+    }
+
+    fn read(&self, buffer: &mut [u8]) -> bool {
+        for byte in buffer.iter_mut() {
+            *byte = self.register(self.next());
+        }
+        true
+    }
+}
@@ -0,0 +1,25 @@
+//! Simulated peripherals for running the kernel and capsules as a normal
+//! Linux process.
+//!
+//! Time comes from the host's monotonic clock, the UART is connected to
+//! stdin and stdout, and GPIO, I2C and SPI are backed by memory so that
+//! capsules can be exercised without hardware. Userspace processes cannot
+//! run on the host, so boards built on this chip run without apps.
+
+#![crate_name = "host"]
+#![crate_type = "rlib"]
+
+extern crate kernel;
+
+pub mod chip;
+pub mod alarm;
+pub mod gpio;
+pub mod i2c;
+pub mod spi;
+pub mod uart;
+
+/// Moves `value` to the heap and returns a reference that lives for the rest
+/// of the program, standing in for `static_init!` on the host.
+pub fn leak<T>(value: T) -> &'static mut T {
+    unsafe { &mut *Box::into_raw(Box::new(value)) }
+}
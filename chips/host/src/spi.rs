@@ -0,0 +1,140 @@
+//! An SPI master with MISO looped back to MOSI, so every byte read is the
+//! byte written at the same time.
+
+use kernel::common::take_cell::TakeCell;
+use kernel::hil::spi::{self, ClockPhase, ClockPolarity};
+use std::cell::Cell;
+
+pub struct Spi {
+    client: Cell<Option<&'static spi::SpiMasterClient>>,
+    rate: Cell<u32>,
+    polarity: Cell<ClockPolarity>,
+    phase: Cell<ClockPhase>,
+    chip_select: Cell<u8>,
+    hold_low: Cell<bool>,
+    write_buffer: TakeCell<'static, [u8]>,
+    read_buffer: TakeCell<'static, [u8]>,
+    len: Cell<usize>,
+    busy: Cell<bool>,
+}
+
+impl Spi {
+    pub fn new() -> Spi {
+        Spi {
+            client: Cell::new(None),
+            rate: Cell::new(0),
+            polarity: Cell::new(ClockPolarity::IdleLow),
+            phase: Cell::new(ClockPhase::SampleLeading),
+            chip_select: Cell::new(0),
+            hold_low: Cell::new(false),
+            write_buffer: TakeCell::empty(),
+            read_buffer: TakeCell::empty(),
+            len: Cell::new(0),
+            busy: Cell::new(false),
+        }
+    }
+
+    /// The chip select line used by the last transfer.
+    pub fn chip_select(&self) -> u8 {
+        self.chip_select.get()
+    }
+
+    pub fn pending(&self) -> bool {
+        self.busy.get()
+    }
+
+    pub fn handle_interrupt(&self) {
+        self.busy.set(false);
+        self.write_buffer.take().map(|write_buffer| {
+            let read_buffer = self.read_buffer.take();
+            self.client.get().map(|client| {
+                client.read_write_done(write_buffer, read_buffer, self.len.get());
+            });
+        });
+    }
+}
+
+impl spi::SpiMaster for Spi {
+    type ChipSelect = u8;
+
+    fn set_client(&self, client: &'static spi::SpiMasterClient) {
+        self.client.set(Some(client));
+    }
+
+    fn init(&self) {}
+
+    fn is_busy(&self) -> bool {
+        self.busy.get()
+    }
+
+    fn read_write_bytes(&self,
+                        write_buffer: &'static mut [u8],
+                        read_buffer: Option<&'static mut [u8]>,
+                        len: usize)
+                        -> bool {
+        if self.busy.get() {
+            return false;
+        }
+
+        let mut len = if len < write_buffer.len() { len } else { write_buffer.len() };
+        read_buffer.map(|read_buffer| {
+            if read_buffer.len() < len {
+                len = read_buffer.len();
+            }
+            read_buffer[..len].copy_from_slice(&write_buffer[..len]);
+            self.read_buffer.replace(read_buffer);
+        });
+
+        self.len.set(len);
+        self.write_buffer.replace(write_buffer);
+        self.busy.set(true);
+        true
+    }
+
+    fn write_byte(&self, _val: u8) {}
+
+    fn read_byte(&self) -> u8 {
+        0
+    }
+
+    fn read_write_byte(&self, val: u8) -> u8 {
+        val
+    }
+
+    fn specify_chip_select(&self, cs: u8) {
+        self.chip_select.set(cs);
+    }
+
+    fn set_rate(&self, rate: u32) -> u32 {
+        self.rate.set(rate);
+        rate
+    }
+
+    fn get_rate(&self) -> u32 {
+        self.rate.get()
+    }
+
+    fn set_clock(&self, polarity: ClockPolarity) {
+        self.polarity.set(polarity);
+    }
+
+    fn get_clock(&self) -> ClockPolarity {
+        self.polarity.get()
+    }
+
+    fn set_phase(&self, phase: ClockPhase) {
+        self.phase.set(phase);
+    }
+
+    fn get_phase(&self) -> ClockPhase {
+        self.phase.get()
+    }
+
+    fn hold_low(&self) {
+        self.hold_low.set(true);
+    }
+
+    fn release_low(&self) {
+        self.hold_low.set(false);
+    }
+}
@@ -0,0 +1,129 @@
+//! A UART connected to the host's stdin and stdout.
+//!
+//! Transmitted bytes are written to stdout straight away and the transmit
+//! completes the next time interrupts are serviced. A thread reads stdin in
+//! the background, and a receive completes once enough bytes have arrived.
+
+use chip::Wakeup;
+use kernel::common::take_cell::TakeCell;
+use kernel::hil::uart::{self, UARTParams};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub struct Uart {
+    client: Cell<Option<&'static uart::Client>>,
+    tx_buffer: TakeCell<'static, [u8]>,
+    rx_buffer: TakeCell<'static, [u8]>,
+    rx_len: Cell<usize>,
+    input: Arc<Mutex<VecDeque<u8>>>,
+    wakeup: Arc<Wakeup>,
+    reader_started: Cell<bool>,
+}
+
+impl Uart {
+    pub fn new(wakeup: Arc<Wakeup>) -> Uart {
+        Uart {
+            client: Cell::new(None),
+            tx_buffer: TakeCell::empty(),
+            rx_buffer: TakeCell::empty(),
+            rx_len: Cell::new(0),
+            input: Arc::new(Mutex::new(VecDeque::new())),
+            wakeup: wakeup,
+            reader_started: Cell::new(false),
+        }
+    }
+
+    fn start_reader(&self) {
+        if self.reader_started.get() {
+            return;
+        }
+        self.reader_started.set(true);
+
+        let input = self.input.clone();
+        let wakeup = self.wakeup.clone();
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            for byte in stdin.lock().bytes() {
+                match byte {
+                    Ok(byte) => {
+                        input.lock().unwrap().push_back(byte);
+                        wakeup.notify();
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+
+    fn tx_pending(&self) -> bool {
+        self.tx_buffer.is_some()
+    }
+
+    fn rx_pending(&self) -> bool {
+        self.rx_buffer.is_some() && self.input.lock().unwrap().len() >= self.rx_len.get()
+    }
+
+    pub fn pending(&self) -> bool {
+        self.tx_pending() || self.rx_pending()
+    }
+
+    pub fn handle_interrupt(&self) {
+        if self.tx_pending() {
+            self.tx_buffer.take().map(|buffer| {
+                self.client.get().map(|client| {
+                    client.transmit_complete(buffer, uart::Error::CommandComplete);
+                });
+            });
+        }
+
+        if self.rx_pending() {
+            self.rx_buffer.take().map(|buffer| {
+                let len = self.rx_len.get();
+                {
+                    let mut input = self.input.lock().unwrap();
+                    for byte in buffer[..len].iter_mut() {
+                        *byte = input.pop_front().unwrap_or(0);
+                    }
+                }
+                self.client.get().map(|client| {
+                    client.receive_complete(buffer, len, uart::Error::CommandComplete);
+                });
+            });
+        }
+    }
+}
+
+impl uart::UART for Uart {
+    fn set_client(&self, client: &'static uart::Client) {
+        self.client.set(Some(client));
+    }
+
+    fn init(&self, _params: UARTParams) {
+        self.start_reader();
+    }
+
+    fn transmit(&self, tx_data: &'static mut [u8], tx_len: usize) {
+        if self.tx_buffer.is_some() {
+            self.client.get().map(|client| {
+                client.transmit_complete(tx_data, uart::Error::RepeatCallError);
+            });
+            return;
+        }
+
+        let len = if tx_len < tx_data.len() { tx_len } else { tx_data.len() };
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = stdout.write_all(&tx_data[..len]);
+        let _ = stdout.flush();
+        self.tx_buffer.replace(tx_data);
+    }
+
+    fn receive(&self, rx_buffer: &'static mut [u8], rx_len: usize) {
+        let len = if rx_len < rx_buffer.len() { rx_len } else { rx_buffer.len() };
+        self.rx_len.set(len);
+        self.rx_buffer.replace(rx_buffer);
+    }
+}
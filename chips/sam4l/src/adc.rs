@@ -67,9 +67,14 @@ pub struct AdcRegisters {
 // Page 59 of SAM4L data sheet
 const BASE_ADDRESS: *mut AdcRegisters = 0x40038000 as *mut AdcRegisters;
 
+// The ADC's bus clock is only enabled while it is being configured or is
+// sampling; conversions themselves run from GCLK10.
+const CLOCK: Clock = Clock::PBA(PBAClock::ADCIFE);
+
 pub struct Adc {
     registers: *mut AdcRegisters,
     enabled: Cell<bool>,
+    sampling: Cell<bool>,
     channel: Cell<u8>,
     client: Cell<Option<&'static hil::adc::Client>>,
 }
@@ -81,6 +86,7 @@ impl Adc {
         Adc {
             registers: base_address,
             enabled: Cell::new(false),
+            sampling: Cell::new(false),
             channel: Cell::new(0),
             client: Cell::new(None),
         }
@@ -103,6 +109,10 @@ impl Adc {
             // Read the value from the LCV register.
             // The sample is 16 bits wide
             val = (regs.lcv.get() & 0xffff) as u16;
+            self.sampling.set(false);
+            unsafe {
+                pm::release_clock(CLOCK);
+            }
             self.client.get().map(|client| { client.sample_done(val); });
         }
     }
@@ -118,7 +128,7 @@ impl adc::AdcSingle for Adc {
             // 1. Start the clocks, ADC uses GCLK10, choose to
             // source it from RCSYS (115Khz)
             unsafe {
-                pm::enable_clock(Clock::PBA(PBAClock::ADCIFE));
+                pm::acquire_clock(CLOCK);
                 nvic::enable(nvic::NvicIdx::ADCIFE);
                 scif::generic_clock_enable(scif::GenericClock::GCLK10, scif::ClockSource::RCSYS);
             }
@@ -146,6 +156,10 @@ impl adc::AdcSingle for Adc {
             //   - the reference voltage to be VCC/2
             regs.cfg.set(0x00000008);
             while regs.sr.get() & (0x51000000) != 0x51000000 {}
+
+            unsafe {
+                pm::release_clock(CLOCK);
+            }
         }
         return ReturnCode::SUCCESS;
     }
@@ -157,6 +171,12 @@ impl adc::AdcSingle for Adc {
         } else if channel > 14 {
             return ReturnCode::EINVAL;
         } else {
+            if !self.sampling.get() {
+                self.sampling.set(true);
+                unsafe {
+                    pm::acquire_clock(CLOCK);
+                }
+            }
             self.channel.set(channel);
             // This configuration sets the ADC to use Pad Ground as the
             // negative input, and the ADC channel as the positive. Since
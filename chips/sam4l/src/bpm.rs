@@ -20,6 +20,12 @@ struct BpmRegisters {
 const BPM_BASE: usize = 0x400F0000;
 const BPM_UNLOCK_KEY: u32 = 0xAA000000;
 
+// Fields of the Power Mode Control register (PMCON)
+const PMCON_BKUP: u32 = 1 << 8;
+const PMCON_RET: u32 = 1 << 9;
+const PMCON_SLEEP_SHIFT: u32 = 12;
+const PMCON_SLEEP_MASK: u32 = 0b11 << PMCON_SLEEP_SHIFT;
+
 static mut BPM: *mut BpmRegisters = BPM_BASE as *mut BpmRegisters;
 
 pub enum CK32Source {
@@ -34,6 +40,50 @@ pub unsafe fn set_ck32source(source: CK32Source) {
     (*BPM).control.set(control | (source as u32) << 16);
 }
 
+/// Sleep modes of the SAM4L, from lightest to deepest (SAM4L Datasheet 6.1.3).
+/// `Retention` and `Backup` are not supported: the first freezes the I/O
+/// lines and the second resets the chip on wakeup.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub enum SleepMode {
+    /// Only the CPU clock is stopped.
+    Sleep0,
+    /// The CPU and AHB clocks are stopped, so DMA cannot run.
+    Sleep1,
+    /// The CPU, AHB, APB and generic clocks are stopped.
+    Sleep2,
+    /// Also stops every clock source except the 32 kHz oscillators.
+    Sleep3,
+    /// Deep sleep with only the 32 kHz oscillators running. The chip wakes
+    /// on asynchronous events such as the AST alarm.
+    Wait,
+}
+
+impl SleepMode {
+    /// Whether the mode needs the Cortex-M4 SLEEPDEEP bit set.
+    pub fn is_deep(&self) -> bool {
+        *self >= SleepMode::Wait
+    }
+}
+
+/// Selects the mode the chip enters on the next `wfi`. For the deep modes,
+/// SLEEPDEEP also has to be set in the System Control Block.
+pub unsafe fn set_sleep_mode(mode: SleepMode) {
+    let sleep = match mode {
+        SleepMode::Sleep0 => 0,
+        SleepMode::Sleep1 => 1,
+        SleepMode::Sleep2 => 2,
+        SleepMode::Sleep3 => 3,
+        // Only SLEEPDEEP distinguishes WAIT from the normal sleep modes
+        SleepMode::Wait => 0,
+    };
+
+    let mut control = (*BPM).control.get();
+    control &= !(PMCON_SLEEP_MASK | PMCON_RET | PMCON_BKUP);
+    control |= sleep << PMCON_SLEEP_SHIFT;
+    unlock_register(0x1c); // Control
+    (*BPM).control.set(control);
+}
+
 unsafe fn unlock_register(register_offset: u32) {
     (*BPM).unlock.set(BPM_UNLOCK_KEY | register_offset);
 }
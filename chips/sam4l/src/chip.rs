@@ -1,5 +1,6 @@
 use adc;
 use ast;
+use bpm::{self, SleepMode};
 use cortexm4;
 use dma;
 use flashcalw;
@@ -7,7 +8,9 @@ use gpio;
 use i2c;
 use kernel::Chip;
 use kernel::common::{RingBuffer, Queue};
+use kernel::support;
 use nvic;
+use pm;
 use spi;
 use trng;
 use usart;
@@ -140,4 +143,26 @@ impl Chip for Sam4l {
     fn systick(&self) -> &cortexm4::systick::SysTick {
         self.systick
     }
+
+    /// Sleeps in the deepest mode that keeps busy peripherals running. The
+    /// AST alarm wakes the chip from any of them.
+    fn sleep(&self) {
+        unsafe {
+            let mut mode = pm::deepest_sleep_mode();
+            if gpio::interrupts_enabled() && mode > SleepMode::Sleep1 {
+                mode = SleepMode::Sleep1;
+            }
+            if !flashcalw::FLASH_CONTROLLER.is_ready() {
+                mode = SleepMode::Sleep0;
+            }
+
+            bpm::set_sleep_mode(mode);
+            if mode.is_deep() {
+                cortexm4::scb::set_sleepdeep();
+            } else {
+                cortexm4::scb::unset_sleepdeep();
+            }
+            support::wfi();
+        }
+    }
 }
@@ -1,5 +1,4 @@
 use core::cell::Cell;
-use core::intrinsics;
 use core::mem;
 
 use kernel::common::take_cell::TakeCell;
@@ -33,9 +32,9 @@ const DMA_BASE_ADDR: usize = 0x400A2000;
 /// The number of bytes between each memory mapped DMA Channel (Section 16.6.1)
 const DMA_CHANNEL_SIZE: usize = 0x40;
 
-/// Shared counter that Keeps track of how many DMA channels are currently
-/// active.
-static mut NUM_ENABLED: usize = 0;
+/// The PDCA clocks, which run while any channel is enabled.
+const HSB_CLOCK: pm::Clock = pm::Clock::HSB(pm::HSBClock::PDCA);
+const PBB_CLOCK: pm::Clock = pm::Clock::PBB(pm::PBBClock::PDCA);
 
 /// The DMA channel number. Each channel transfers data between memory and a
 /// particular peripheral function (e.g., SPI read or SPI write, but not both
@@ -151,17 +150,10 @@ impl DMAChannel {
     }
 
     pub fn enable(&self) {
-        unsafe {
-            pm::enable_clock(pm::Clock::HSB(pm::HSBClock::PDCA));
-            pm::enable_clock(pm::Clock::PBB(pm::PBBClock::PDCA));
-        }
         if !self.enabled.get() {
             unsafe {
-                let num_enabled = intrinsics::atomic_xadd(&mut NUM_ENABLED, 1);
-                if num_enabled == 1 {
-                    pm::enable_clock(pm::Clock::HSB(pm::HSBClock::PDCA));
-                    pm::enable_clock(pm::Clock::PBB(pm::PBBClock::PDCA));
-                }
+                pm::acquire_clock(HSB_CLOCK);
+                pm::acquire_clock(PBB_CLOCK);
             }
             let registers: &mut DMARegisters = unsafe { mem::transmute(self.registers) };
             registers.interrupt_disable.set(0xffffffff);
@@ -174,18 +166,13 @@ impl DMAChannel {
 
     pub fn disable(&self) {
         if self.enabled.get() {
-            unsafe {
-                let num_enabled = intrinsics::atomic_xsub(&mut NUM_ENABLED, 1);
-                if num_enabled == 1 {
-                    pm::disable_clock(pm::Clock::HSB(pm::HSBClock::PDCA));
-                    pm::disable_clock(pm::Clock::PBB(pm::PBBClock::PDCA));
-                }
-            }
             let registers: &mut DMARegisters = unsafe { mem::transmute(self.registers) };
             registers.control.set(0x2);
             self.enabled.set(false);
             unsafe {
                 nvic::disable(self.nvic);
+                pm::release_clock(HSB_CLOCK);
+                pm::release_clock(PBB_CLOCK);
             }
         }
     }
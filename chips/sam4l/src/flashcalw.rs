@@ -26,7 +26,9 @@
 use core::cell::Cell;
 use core::mem;
 use kernel::common::VolatileCell;
-use kernel::common::take_cell::MapCell;
+use kernel::common::take_cell::{MapCell, TakeCell};
+use kernel::hil;
+use kernel::returncode::ReturnCode;
 use nvic;
 use pm;
 
@@ -150,6 +152,16 @@ pub enum FlashState {
     Unconfigured, // The Flash is unconfigured, call configure()
 }
 
+/// Operation requested through the `hil::flash::Flash` interface, tracked so
+/// that its client can be told when it finishes.
+#[derive(Clone, Copy, PartialEq)]
+enum HilOperation {
+    None,
+    Write,
+    /// Erasing pages up to, but not including, `end`.
+    Erase { page: i32, end: i32 },
+}
+
 // The FLASHCALW controller
 pub struct FLASHCALW {
     registers: *mut Registers,
@@ -163,6 +175,9 @@ pub struct FLASHCALW {
     current_state: Cell<FlashState>,
     current_command: Cell<Command>,
     page_buffer: MapCell<[u8; PAGE_SIZE as usize]>,
+    hil_client: Cell<Option<&'static hil::flash::Client>>,
+    hil_operation: Cell<HilOperation>,
+    hil_buffer: TakeCell<'static, [u8]>,
 }
 
 // static instance for the board. Only one FLASHCALW on chip.
@@ -219,6 +234,9 @@ impl FLASHCALW {
             current_state: Cell::new(FlashState::Unconfigured),
             current_command: Cell::new(Command::None),
             page_buffer: MapCell::new([0; PAGE_SIZE as usize]),
+            hil_client: Cell::new(None),
+            hil_operation: Cell::new(HilOperation::None),
+            hil_buffer: TakeCell::empty(),
         }
     }
 
@@ -295,6 +313,7 @@ impl FLASHCALW {
                     _ => {}
                 }
             });
+            self.hil_command_complete(ReturnCode::FAIL);
         }
 
         //  Part of a command succeeded -- continue onto next steps.
@@ -362,6 +381,38 @@ impl FLASHCALW {
         if self.current_command.get() == Command::None &&
            self.current_state.get() == FlashState::Ready {
             self.client.get().map(|value| { value.command_complete(Error::CommandComplete); });
+            self.hil_command_complete(ReturnCode::SUCCESS);
+        }
+    }
+
+    /// Finishes, or continues, the operation requested through the
+    /// `hil::flash::Flash` interface, if there is one.
+    fn hil_command_complete(&self, result: ReturnCode) {
+        match self.hil_operation.get() {
+            HilOperation::None => {}
+            HilOperation::Write => {
+                self.hil_operation.set(HilOperation::None);
+                self.hil_buffer.take().map(|buffer| {
+                    self.hil_client.get().map(move |client| client.write_complete(buffer, result));
+                });
+            }
+            HilOperation::Erase { page, end } => {
+                if result == ReturnCode::SUCCESS && page + 1 < end &&
+                   self.erase_page(page + 1) == 0 {
+                    self.hil_operation.set(HilOperation::Erase {
+                        page: page + 1,
+                        end: end,
+                    });
+                    return;
+                }
+                self.hil_operation.set(HilOperation::None);
+                let result = if page + 1 < end {
+                    ReturnCode::FAIL
+                } else {
+                    result
+                };
+                self.hil_client.get().map(|client| client.erase_complete(result));
+            }
         }
     }
 
@@ -866,6 +917,68 @@ impl FLASHCALW {
     }
 }
 
+/// Flash offsets are addresses, as the flash is mapped at address 0.
+///
+/// Reads complete before `read` returns, and writes and erases must be page
+/// aligned. Argument errors are reported to the client before the call
+/// returns.
+impl hil::flash::Flash for FLASHCALW {
+    fn set_client(&self, client: &'static hil::flash::Client) {
+        self.hil_client.set(Some(client));
+    }
+
+    fn read(&self, offset: usize, buf: &'static mut [u8]) {
+        let len = buf.len();
+        let result = if FLASHCALW::read(self, offset, len, buf) == 0 {
+            ReturnCode::SUCCESS
+        } else {
+            ReturnCode::EINVAL
+        };
+        self.hil_client.get().map(move |client| client.read_complete(buf, result));
+    }
+
+    fn write(&self, offset: usize, buf: &'static mut [u8]) {
+        let page_size = PAGE_SIZE as usize;
+        if offset % page_size != 0 || buf.len() != page_size ||
+           offset + page_size > self.get_flash_size() as usize {
+            self.hil_client
+                .get()
+                .map(move |client| client.write_complete(buf, ReturnCode::EINVAL));
+            return;
+        }
+        if self.write_page((offset / page_size) as i32, buf) != 0 {
+            self.hil_client
+                .get()
+                .map(move |client| client.write_complete(buf, ReturnCode::FAIL));
+            return;
+        }
+        self.hil_buffer.replace(buf);
+        self.hil_operation.set(HilOperation::Write);
+    }
+
+    fn erase(&self, offset: usize, len: usize) {
+        let page_size = PAGE_SIZE as usize;
+        if offset % page_size != 0 || len % page_size != 0 || len == 0 ||
+           offset + len > self.get_flash_size() as usize {
+            self.hil_client
+                .get()
+                .map(|client| client.erase_complete(ReturnCode::EINVAL));
+            return;
+        }
+        let page = (offset / page_size) as i32;
+        if self.erase_page(page) != 0 {
+            self.hil_client
+                .get()
+                .map(|client| client.erase_complete(ReturnCode::FAIL));
+            return;
+        }
+        self.hil_operation.set(HilOperation::Erase {
+            page: page,
+            end: page + (len / page_size) as i32,
+        });
+    }
+}
+
 ///  Assumes the only Peripheral Interrupt enabled for the FLASHCALW is the
 ///  FRDY (Flash Ready) interrupt.
 pub unsafe extern "C" fn flash_handler() {
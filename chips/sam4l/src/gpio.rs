@@ -142,6 +142,12 @@ impl IndexMut<usize> for Port {
 }
 
 impl Port {
+    /// Whether any pin on the port has its interrupt enabled.
+    pub fn interrupts_enabled(&self) -> bool {
+        let port: &Registers = unsafe { mem::transmute(self.port) };
+        port.ier.val.get() != 0
+    }
+
     pub fn handle_interrupt(&self) {
         let port: &mut Registers = unsafe { mem::transmute(self.port) };
 
@@ -165,6 +171,12 @@ impl Port {
     }
 }
 
+/// Whether any pin has its interrupt enabled. Pin interrupts need the GPIO
+/// controller's clock, which stops in the deeper sleep modes.
+pub unsafe fn interrupts_enabled() -> bool {
+    PA.interrupts_enabled() || PB.interrupts_enabled() || PC.interrupts_enabled()
+}
+
 /// Port A
 pub static mut PA: Port = Port {
     port: (BASE_ADDRESS + 0 * SIZE) as *mut Registers,
@@ -263,6 +263,7 @@ impl I2CHw {
                     regs.control.set(0x1 << 0);
                     regs.control.set(0x1 << 7);
                     regs.control.set(0x1 << 1);
+                    self.transaction_done();
 
                     self.master_client.get().map(|client| {
                         let buf = match self.dma.get() {
@@ -290,10 +291,13 @@ impl I2CHw {
                     regs.next_command.set(0);
 
                     err.map(|err| {
+                        let byte = regs.receive_holding.get() as u8;
+
                         // enable, reset, disable
                         regs.control.set(0x1 << 0);
                         regs.control.set(0x1 << 7);
                         regs.control.set(0x1 << 1);
+                        self.transaction_done();
 
                         self.master_client.get().map(|client| {
                             let buf = match self.dma.get() {
@@ -306,7 +310,7 @@ impl I2CHw {
                             };
                             buf.map(|buf| {
                                 // Save the already read byte.
-                                buf[0] = regs.receive_holding.get() as u8;
+                                buf[0] = byte;
                                 client.command_complete(buf, err);
                             });
                         });
@@ -371,6 +375,14 @@ impl I2CHw {
         regs.control.set(0x1 << 0);
     }
 
+    /// Gates the TWIM clock again once a master transaction has finished.
+    fn transaction_done(&self) {
+        self.dma.get().map(|dma| dma.disable());
+        unsafe {
+            pm::release_clock(self.master_clock);
+        }
+    }
+
     fn master_enable(&self) {
         let regs: &mut TWIMRegisters = unsafe { mem::transmute(self.registers) };
 
@@ -381,6 +393,9 @@ impl I2CHw {
 
     pub fn write(&self, chip: u8, flags: usize, data: &'static mut [u8], len: u8) {
         self.dma.get().map(move |dma| {
+            unsafe {
+                pm::acquire_clock(self.master_clock);
+            }
             dma.enable();
             dma.prepare_xfer(self.dma_pids.1, data, len as usize);
             self.setup_xfer(chip, flags, false, len);
@@ -391,6 +406,9 @@ impl I2CHw {
 
     pub fn read(&self, chip: u8, flags: usize, data: &'static mut [u8], len: u8) {
         self.dma.get().map(move |dma| {
+            unsafe {
+                pm::acquire_clock(self.master_clock);
+            }
             dma.enable();
             dma.prepare_xfer(self.dma_pids.0, data, len as usize);
             self.setup_xfer(chip, flags, true, len);
@@ -401,6 +419,9 @@ impl I2CHw {
 
     pub fn write_read(&self, chip: u8, data: &'static mut [u8], split: u8, read_len: u8) {
         self.dma.get().map(move |dma| {
+            unsafe {
+                pm::acquire_clock(self.master_clock);
+            }
             dma.enable();
             dma.prepare_xfer(self.dma_pids.1, data, split as usize);
             self.setup_xfer(chip, START, false, split);
@@ -734,31 +755,32 @@ impl DMAClient for I2CHw {
 impl hil::i2c::I2CMaster for I2CHw {
     /// This enables the entire I2C peripheral
     fn enable(&self) {
-        // Enable the clock for the TWIM module
-        unsafe {
-            pm::enable_clock(self.master_clock);
+        // If the slave is listening, stop it so it releases its clock
+        if self.slave_enabled.get() {
+            hil::i2c::I2CSlave::disable(self);
         }
 
-        // If exists, disable slave clock
-        self.slave_clock.map(|slave_clock| unsafe {
-            pm::disable_clock(slave_clock);
-        });
-
         let regs: &mut TWIMRegisters = unsafe { mem::transmute(self.registers) };
 
-        // enable, reset, disable
-        regs.control.set(0x1 << 0);
-        regs.control.set(0x1 << 7);
-        regs.control.set(0x1 << 1);
+        // The TWIM clock only runs during transactions, so enable it just
+        // long enough to configure the peripheral
+        unsafe {
+            pm::with_clock(self.master_clock, || {
+                // enable, reset, disable
+                regs.control.set(0x1 << 0);
+                regs.control.set(0x1 << 7);
+                regs.control.set(0x1 << 1);
 
-        // Init the bus speed
-        self.set_bus_speed();
+                // Init the bus speed
+                self.set_bus_speed();
 
-        // slew
-        regs.slew_rate.set((0x2 << 28) | (7 << 16) | (7 << 0));
+                // slew
+                regs.slew_rate.set((0x2 << 28) | (7 << 16) | (7 << 0));
 
-        // clear interrupts
-        regs.status_clear.set(!0);
+                // clear interrupts
+                regs.status_clear.set(!0);
+            });
+        }
 
         self.enable_interrupts();
     }
@@ -766,11 +788,12 @@ impl hil::i2c::I2CMaster for I2CHw {
     /// This disables the entire I2C peripheral
     fn disable(&self) {
         let regs: &mut TWIMRegisters = unsafe { mem::transmute(self.registers) };
-        regs.control.set(0x1 << 1);
         unsafe {
-            pm::disable_clock(self.master_clock);
+            pm::with_clock(self.master_clock, || {
+                regs.control.set(0x1 << 1);
+                self.disable_interrupts();
+            });
         }
-        self.disable_interrupts();
     }
 
     fn write(&self, addr: u8, data: &'static mut [u8], len: u8) {
@@ -788,9 +811,13 @@ impl hil::i2c::I2CMaster for I2CHw {
 
 impl hil::i2c::I2CSlave for I2CHw {
     fn enable(&self) {
+        // The slave has to be clocked for as long as it is listening, so it
+        // holds its clock until it is disabled
+        if self.slave_enabled.get() {
+            return;
+        }
         self.slave_clock.map(|slave_clock| unsafe {
-            pm::disable_clock(self.master_clock);
-            pm::enable_clock(slave_clock);
+            pm::acquire_clock(slave_clock);
         });
 
         self.slave_registers.map(|slave_registers| {
@@ -824,17 +851,20 @@ impl hil::i2c::I2CSlave for I2CHw {
 
     /// This disables the entire I2C peripheral
     fn disable(&self) {
+        if !self.slave_enabled.get() {
+            return;
+        }
         self.slave_enabled.set(false);
 
         self.slave_registers.map(|slave_registers| {
             let regs: &mut TWISRegisters = unsafe { mem::transmute(slave_registers) };
 
             regs.control.set(0);
-            self.slave_clock.map(|slave_clock| unsafe {
-                pm::disable_clock(slave_clock);
-            });
         });
         self.slave_disable_interrupts();
+        self.slave_clock.map(|slave_clock| unsafe {
+            pm::release_clock(slave_clock);
+        });
     }
 
     fn set_address(&self, addr: u8) {
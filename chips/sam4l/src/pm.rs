@@ -1,3 +1,4 @@
+use bpm::SleepMode;
 use kernel::common::volatile_cell::VolatileCell;
 
 #[repr(C, packed)]
@@ -496,11 +497,140 @@ pub unsafe fn enable_clock(clock: Clock) {
     }
 }
 
+macro_rules! clear_clock_mask {
+    ($module:ident: $field:ident & $mask:expr) => ({
+        unlock(concat_idents!($module, _MASK_OFFSET));
+        let val = (*PM).$field.get() & ($mask);
+        (*PM).$field.set(val);
+    });
+}
+
 pub unsafe fn disable_clock(clock: Clock) {
     match clock {
-        Clock::HSB(v) => mask_clock!(HSB: hsbmask | !(1 << (v as u32))),
-        Clock::PBA(v) => mask_clock!(PBA: pbamask | !(1 << (v as u32))),
-        Clock::PBB(v) => mask_clock!(PBB: pbbmask | !(1 << (v as u32))),
-        Clock::PBD(v) => mask_clock!(PBD: pbdmask | !(1 << (v as u32))),
+        Clock::HSB(v) => clear_clock_mask!(HSB: hsbmask & !(1 << (v as u32))),
+        Clock::PBA(v) => clear_clock_mask!(PBA: pbamask & !(1 << (v as u32))),
+        Clock::PBB(v) => clear_clock_mask!(PBB: pbbmask & !(1 << (v as u32))),
+        Clock::PBD(v) => clear_clock_mask!(PBD: pbdmask & !(1 << (v as u32))),
+    }
+}
+
+/// Number of outstanding `acquire_clock` calls for each clock, indexed by bus
+/// (HSB, PBA, PBB, PBD) and then by the clock's bit in that bus's mask.
+static mut CLOCK_USERS: [[u8; 32]; 4] = [[0; 32]; 4];
+
+fn clock_index(clock: Clock) -> (usize, usize) {
+    match clock {
+        Clock::HSB(v) => (0, v as usize),
+        Clock::PBA(v) => (1, v as usize),
+        Clock::PBB(v) => (2, v as usize),
+        Clock::PBD(v) => (3, v as usize),
+    }
+}
+
+/// Registers a user of `clock`, enabling the clock if nothing else was using
+/// it.
+///
+/// Drivers acquire their peripheral's clock when a transaction starts and
+/// release it when the transaction completes or is aborted, so the clock
+/// only runs while the peripheral has work to do. Peripheral registers keep
+/// their values while the clock is masked.
+pub unsafe fn acquire_clock(clock: Clock) {
+    let (bus, bit) = clock_index(clock);
+    CLOCK_USERS[bus][bit] += 1;
+    if CLOCK_USERS[bus][bit] == 1 {
+        enable_clock(clock);
+    }
+}
+
+/// Drops a user of `clock`, disabling the clock once its last user has
+/// released it. Releasing a clock that has no users does nothing.
+pub unsafe fn release_clock(clock: Clock) {
+    let (bus, bit) = clock_index(clock);
+    if CLOCK_USERS[bus][bit] > 0 {
+        CLOCK_USERS[bus][bit] -= 1;
+        if CLOCK_USERS[bus][bit] == 0 {
+            disable_clock(clock);
+        }
+    }
+}
+
+/// Runs `f` with `clock` enabled. This is for register accesses outside of
+/// a transaction, such as configuration, that should not keep the clock on
+/// afterwards.
+pub unsafe fn with_clock<F, R>(clock: Clock, f: F) -> R
+    where F: FnOnce() -> R
+{
+    acquire_clock(clock);
+    let result = f();
+    release_clock(clock);
+    result
+}
+
+/// Returns how many drivers currently hold `clock`.
+pub unsafe fn clock_users(clock: Clock) -> u8 {
+    let (bus, bit) = clock_index(clock);
+    CLOCK_USERS[bus][bit]
+}
+
+/// The set of enabled peripheral clocks, one bit per clock in the same layout
+/// as the PM mask registers.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct ActiveClocks {
+    pub hsb: u32,
+    pub pba: u32,
+    pub pbb: u32,
+    pub pbd: u32,
+}
+
+impl ActiveClocks {
+    pub fn is_enabled(&self, clock: Clock) -> bool {
+        match clock {
+            Clock::HSB(v) => self.hsb & (1 << (v as u32)) != 0,
+            Clock::PBA(v) => self.pba & (1 << (v as u32)) != 0,
+            Clock::PBB(v) => self.pbb & (1 << (v as u32)) != 0,
+            Clock::PBD(v) => self.pbd & (1 << (v as u32)) != 0,
+        }
+    }
+}
+
+/// Reports which peripheral clocks are currently enabled, whether through
+/// `acquire_clock` or `enable_clock`.
+pub unsafe fn active_clocks() -> ActiveClocks {
+    ActiveClocks {
+        hsb: (*PM).hsbmask.get(),
+        pba: (*PM).pbamask.get(),
+        pbb: (*PM).pbbmask.get(),
+        pbd: (*PM).pbdmask.get(),
+    }
+}
+
+/// HSB clocks that are on whenever code runs from flash, so they do not mean
+/// a peripheral is busy.
+const HSB_ALWAYS_ON: u32 = (1 << (HSBClock::FLASHCALW as u32)) |
+                           (1 << (HSBClock::FLASHCALWP as u32)) |
+                           (1 << (HSBClock::APBA as u32)) |
+                           (1 << (HSBClock::APBB as u32)) |
+                           (1 << (HSBClock::APBC as u32)) |
+                           (1 << (HSBClock::APBD as u32));
+
+/// PBB clocks that are on whenever code runs from flash.
+const PBB_ALWAYS_ON: u32 = (1 << (PBBClock::FLASHCALW as u32)) |
+                           (1 << (PBBClock::HRAMC1 as u32)) |
+                           (1 << (PBBClock::HMATRIX as u32));
+
+/// Returns the deepest sleep mode that keeps every peripheral whose clock is
+/// enabled running.
+pub unsafe fn deepest_sleep_mode() -> SleepMode {
+    let active = active_clocks();
+    let hsb = active.hsb & !HSB_ALWAYS_ON;
+    let pbb = active.pbb & !PBB_ALWAYS_ON;
+    if hsb != 0 || pbb != 0 {
+        // DMA and the other bus masters need the AHB clock
+        SleepMode::Sleep0
+    } else if active.pba != 0 {
+        SleepMode::Sleep1
+    } else {
+        // The PBD peripherals, such as the AST, run from the 32 kHz clock
+        SleepMode::Wait
     }
 }
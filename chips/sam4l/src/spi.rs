@@ -46,6 +46,7 @@ struct SpiRegisters {
 }
 
 const SPI_BASE: u32 = 0x40008000;
+const CLOCK: pm::Clock = pm::Clock::PBA(pm::PBAClock::SPI);
 
 /// Values for selected peripherals
 #[derive(Copy,Clone)]
@@ -88,9 +89,8 @@ impl Spi {
         let regs: &mut SpiRegisters = unsafe { mem::transmute(self.registers) };
 
         unsafe {
-            pm::enable_clock(pm::Clock::PBA(pm::PBAClock::SPI));
+            pm::with_clock(CLOCK, || regs.cr.set(0b1));
         }
-        regs.cr.set(0b1);
     }
 
     pub fn disable(&self) {
@@ -98,7 +98,9 @@ impl Spi {
 
         self.dma_read.get().map(|read| read.disable());
         self.dma_write.get().map(|write| write.disable());
-        regs.cr.set(0b10);
+        unsafe {
+            pm::with_clock(CLOCK, || regs.cr.set(0b10));
+        }
     }
 
     /// Sets the approximate baud rate for the active peripheral,
@@ -153,18 +155,22 @@ impl Spi {
             Peripheral::Peripheral2 => 0b1011,
             Peripheral::Peripheral3 => 0b0111,
         };
-        let mut mr = regs.mr.get();
-        let pcs_mask: u32 = 0xFFF0FFFF;
-        mr &= pcs_mask;
-        mr |= peripheral_number << 16;
-        regs.mr.set(mr);
+        unsafe {
+            pm::with_clock(CLOCK, || {
+                let mut mr = regs.mr.get();
+                let pcs_mask: u32 = 0xFFF0FFFF;
+                mr &= pcs_mask;
+                mr |= peripheral_number << 16;
+                regs.mr.set(mr);
+            });
+        }
     }
 
     /// Returns the currently active peripheral
     pub fn get_active_peripheral(&self) -> Peripheral {
         let regs: &mut SpiRegisters = unsafe { mem::transmute(self.registers) };
 
-        let mr = regs.mr.get();
+        let mr = unsafe { pm::with_clock(CLOCK, || regs.mr.get()) };
         let pcs = (mr >> 16) & 0xF;
         // Split into bits for matching
         match pcs {
@@ -185,11 +191,14 @@ impl Spi {
     fn read_active_csr(&self) -> u32 {
         let regs: &mut SpiRegisters = unsafe { mem::transmute(self.registers) };
 
-        match self.get_active_peripheral() {
-            Peripheral::Peripheral0 => regs.csr0.get(),
-            Peripheral::Peripheral1 => regs.csr1.get(),
-            Peripheral::Peripheral2 => regs.csr2.get(),
-            Peripheral::Peripheral3 => regs.csr3.get(),
+        let peripheral = self.get_active_peripheral();
+        unsafe {
+            pm::with_clock(CLOCK, || match peripheral {
+                Peripheral::Peripheral0 => regs.csr0.get(),
+                Peripheral::Peripheral1 => regs.csr1.get(),
+                Peripheral::Peripheral2 => regs.csr2.get(),
+                Peripheral::Peripheral3 => regs.csr3.get(),
+            })
         }
     }
     /// Sets the Chip Select Register (CSR) of the active peripheral
@@ -197,12 +206,15 @@ impl Spi {
     fn write_active_csr(&self, value: u32) {
         let regs: &mut SpiRegisters = unsafe { mem::transmute(self.registers) };
 
-        match self.get_active_peripheral() {
-            Peripheral::Peripheral0 => regs.csr0.set(value),
-            Peripheral::Peripheral1 => regs.csr1.set(value),
-            Peripheral::Peripheral2 => regs.csr2.set(value),
-            Peripheral::Peripheral3 => regs.csr3.set(value),
-        };
+        let peripheral = self.get_active_peripheral();
+        unsafe {
+            pm::with_clock(CLOCK, || match peripheral {
+                Peripheral::Peripheral0 => regs.csr0.set(value),
+                Peripheral::Peripheral1 => regs.csr1.set(value),
+                Peripheral::Peripheral2 => regs.csr2.set(value),
+                Peripheral::Peripheral3 => regs.csr3.set(value),
+            });
+        }
     }
 
     /// Set the DMA channels used for reading and writing.
@@ -210,12 +222,6 @@ impl Spi {
         self.dma_read.set(Some(read));
         self.dma_write.set(Some(write));
     }
-
-    fn enable_clock(&self) {
-        unsafe {
-            pm::enable_clock(pm::Clock::PBA(pm::PBAClock::SPI));
-        }
-    }
 }
 
 impl spi::SpiMaster for Spi {
@@ -230,13 +236,16 @@ impl spi::SpiMaster for Spi {
     fn init(&self) {
         let regs: &mut SpiRegisters = unsafe { mem::transmute(self.registers) };
 
-        self.enable_clock();
-        regs.cr.set(1 << 24);
+        unsafe {
+            pm::with_clock(CLOCK, || {
+                regs.cr.set(1 << 24);
 
-        let mut mode = regs.mr.get();
-        mode |= 1; // Enable master mode
-        mode |= 1 << 4; // Disable mode fault detection (open drain outputs not supported)
-        regs.mr.set(mode);
+                let mut mode = regs.mr.get();
+                mode |= 1; // Enable master mode
+                mode |= 1 << 4; // Disable mode fault detection (open drain outputs not supported)
+                regs.mr.set(mode);
+            });
+        }
     }
 
     fn is_busy(&self) -> bool {
@@ -253,10 +262,14 @@ impl spi::SpiMaster for Spi {
         }
 
         let tdr = out_byte as u32;
-        // Wait for data to leave TDR and enter serializer, so TDR is free
-        // for this next byte
-        while (regs.sr.get() & (1 << 1)) == 0 {}
-        regs.tdr.set(tdr);
+        unsafe {
+            pm::with_clock(CLOCK, || {
+                // Wait for data to leave TDR and enter serializer, so TDR is free
+                // for this next byte
+                while (regs.sr.get() & (1 << 1)) == 0 {}
+                regs.tdr.set(tdr);
+            });
+        }
     }
 
     /// Write 0 to the SPI and return the read; if an
@@ -273,11 +286,15 @@ impl spi::SpiMaster for Spi {
         if self.is_busy() {
             //          return 0;
         }
-        self.write_byte(val);
-        // Wait for receive data register full
-        while (regs.sr.get() & 1) != 1 {}
-        // Return read value
-        regs.rdr.get() as u8
+        unsafe {
+            pm::with_clock(CLOCK, || {
+                self.write_byte(val);
+                // Wait for receive data register full
+                while (regs.sr.get() & 1) != 1 {}
+                // Return read value
+                regs.rdr.get() as u8
+            })
+        }
     }
 
     /// Asynchronous buffer read/write of SPI.
@@ -301,6 +318,9 @@ impl spi::SpiMaster for Spi {
 
         // We will have at least a write transfer in progress
         self.transfers_in_progress.set(1);
+        unsafe {
+            pm::acquire_clock(CLOCK);
+        }
 
         let read_len = match read_buffer {
             Some(ref buf) => buf.len(),
@@ -427,6 +447,10 @@ impl DMAClient for Spi {
                 buf
             });
 
+            unsafe {
+                pm::release_clock(CLOCK);
+            }
+
             let len = self.dma_length.get();
             self.dma_length.set(0);
             self.client
@@ -19,10 +19,12 @@ struct Registers {
 }
 
 const BASE_ADDRESS: *const Registers = 0x40068000 as *const Registers;
+const CLOCK: pm::Clock = pm::Clock::PBA(pm::PBAClock::TRNG);
 
 pub struct Trng<'a> {
     regs: *const Registers,
     client: Cell<Option<&'a rng::Client>>,
+    running: Cell<bool>,
 }
 
 pub static mut TRNG: Trng<'static> = Trng::new();
@@ -33,6 +35,7 @@ impl<'a> Trng<'a> {
         Trng {
             regs: BASE_ADDRESS,
             client: Cell::new(None),
+            running: Cell::new(false),
         }
     }
 
@@ -49,8 +52,9 @@ impl<'a> Trng<'a> {
             if let Continue::Done = result {
                 // disable controller
                 regs.control.set(KEY | 0);
+                self.running.set(false);
                 unsafe {
-                    pm::disable_clock(pm::Clock::PBA(pm::PBAClock::TRNG));
+                    pm::release_clock(CLOCK);
                 }
             } else {
                 regs.interrupt_enable.set(1);
@@ -81,8 +85,13 @@ impl<'a, 'b> Iterator for TrngIter<'a, 'b> {
 impl<'a> rng::RNG for Trng<'a> {
     fn get(&self) {
         let regs = unsafe { &*self.regs };
+        if !self.running.get() {
+            self.running.set(true);
+            unsafe {
+                pm::acquire_clock(CLOCK);
+            }
+        }
         unsafe {
-            pm::enable_clock(pm::Clock::PBA(pm::PBAClock::TRNG));
             nvic::enable(nvic::NvicIdx::TRNG);
         }
 
@@ -157,29 +157,71 @@ impl USART {
     pub fn enable_rx(&self) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
         let cr_val = 0x00000000 | (1 << 4); // RXEN
-        regs.cr.set(cr_val);
+        self.with_clock(|| regs.cr.set(cr_val));
     }
 
     pub fn enable_tx(&self) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
         let cr_val = 0x00000000 | (1 << 6); // TXEN
-        regs.cr.set(cr_val);
+        self.with_clock(|| regs.cr.set(cr_val));
     }
 
     pub fn disable_rx(&self) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
         let cr_val = 0x00000000 | (1 << 5); // RXDIS
-        regs.cr.set(cr_val);
+        self.with_clock(|| regs.cr.set(cr_val));
 
-        self.usart_rx_state.set(USARTStateRX::Idle);
+        if self.usart_rx_state.get() != USARTStateRX::Idle {
+            self.usart_rx_state.set(USARTStateRX::Idle);
+            self.release_clock();
+        }
     }
 
     pub fn disable_tx(&self) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
         let cr_val = 0x00000000 | (1 << 7); // TXDIS
-        regs.cr.set(cr_val);
+        self.with_clock(|| regs.cr.set(cr_val));
+
+        if self.usart_tx_state.get() != USARTStateTX::Idle {
+            self.usart_tx_state.set(USARTStateTX::Idle);
+            self.release_clock();
+        }
+    }
+
+    /// Marks a reception as in progress. Each direction holds a reference on
+    /// the USART clock from when it starts until it returns to idle.
+    fn start_rx(&self) {
+        if self.usart_rx_state.get() == USARTStateRX::Idle {
+            self.acquire_clock();
+        }
+        self.usart_rx_state.set(USARTStateRX::DMA_Receiving);
+    }
+
+    /// Marks a transmission as in progress. If the previous transmission is
+    /// still shifting out its last byte, its clock reference carries over.
+    fn start_tx(&self) {
+        match self.usart_tx_state.get() {
+            USARTStateTX::Idle => self.acquire_clock(),
+            USARTStateTX::Transfer_Completing => self.disable_tx_interrupts(),
+            USARTStateTX::DMA_Transmitting => {}
+        }
+        self.usart_tx_state.set(USARTStateTX::DMA_Transmitting);
+    }
 
-        self.usart_tx_state.set(USARTStateTX::Idle);
+    /// Called once the DMA has handed over the last byte of a transmission.
+    /// The clock is kept on until the TXEMPTY interrupt reports that the
+    /// byte has left the shift register.
+    fn complete_tx(&self) {
+        let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
+        self.usart_tx_state.set(USARTStateTX::Transfer_Completing);
+        regs.ier.set(1 << 9); // TXEMPTY
+        self.enable_nvic();
+    }
+
+    /// Called on TXEMPTY after `complete_tx`, or when resetting mid-transfer.
+    fn finish_tx(&self) {
+        self.disable_tx_interrupts();
+        self.disable_tx();
     }
 
     pub fn abort_rx(&self, error: hil::uart::Error) {
@@ -245,7 +287,7 @@ impl USART {
             (1 <<  7) | // PARE
             (1 <<  6) | // FRAME
             (1 <<  5); //. OVRE
-        regs.ier.set(ier_val);
+        self.with_clock(|| regs.ier.set(ier_val));
     }
 
     pub fn disable_rx_interrupts(&self) {
@@ -257,7 +299,7 @@ impl USART {
             (1 <<  6) | // FRAME
             (1 <<  5) | // OVRE
             (1 << 1); //.. RXRDY
-        regs.idr.set(idr_val);
+        self.with_clock(|| regs.idr.set(idr_val));
 
         // XXX: disable nvic if no interrupts are enabled
     }
@@ -267,7 +309,7 @@ impl USART {
         let idr_val = 0x00000000 |
             (1 << 9) | // TXEMPTY
             (1 << 1); //. TXREADY
-        regs.idr.set(idr_val);
+        self.with_clock(|| regs.idr.set(idr_val));
 
         // XXX: disable nvic if no interrupts are enabled
     }
@@ -286,16 +328,29 @@ impl USART {
             (1 << 8) | // RSTSTA
             (1 << 3) | // RSTTX
             (1 <<2); //.. RSTRX
-        regs.cr.set(cr_val);
+        self.with_clock(|| regs.cr.set(cr_val));
 
         self.abort_rx(hil::uart::Error::ResetError);
         self.abort_tx(hil::uart::Error::ResetError);
+        if self.usart_tx_state.get() == USARTStateTX::Transfer_Completing {
+            self.finish_tx();
+        }
     }
 
     pub fn handle_interrupt(&self) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
+
+        // Finishing a transfer may drop the last reference on the clock, so
+        // hold one until the status registers have been reset
+        self.acquire_clock();
         let status = regs.csr.get();
 
+        if self.usart_tx_state.get() == USARTStateTX::Transfer_Completing &&
+           status & (1 << 9) != 0 {
+            // TXEMPTY
+            self.finish_tx();
+        }
+
         if status & (1 << 12) != 0 {
             // DO NOTHING. Why are we here!?
 
@@ -319,14 +374,29 @@ impl USART {
 
         // reset status registers
         regs.cr.set(1 << 8); // RSTSTA
+        self.release_clock();
     }
 
-    fn enable_clock(&self) {
+    fn acquire_clock(&self) {
         unsafe {
-            pm::enable_clock(self.clock);
+            pm::acquire_clock(self.clock);
         }
     }
 
+    fn release_clock(&self) {
+        unsafe {
+            pm::release_clock(self.clock);
+        }
+    }
+
+    /// Runs `f` with the USART clock enabled, for register accesses that may
+    /// happen while no transfer is in progress.
+    fn with_clock<F, R>(&self, f: F) -> R
+        where F: FnOnce() -> R
+    {
+        unsafe { pm::with_clock(self.clock, f) }
+    }
+
     fn enable_nvic(&self) {
         unsafe {
             nvic::enable(self.nvic);
@@ -403,13 +473,17 @@ impl USART {
     pub fn send_byte(&self, byte: u8) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
         let thr_val: u32 = 0x00000000 | byte as u32;
-        regs.thr.set(thr_val);
+        self.with_clock(|| {
+            regs.thr.set(thr_val);
+            // keep the clock running until the byte is on the wire
+            while regs.csr.get() & (1 << 9) == 0 {} // TXEMPTY
+        });
     }
 
     // for use by panic in io.rs
     pub fn tx_ready(&self) -> bool {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        let csr_val: u32 = regs.csr.get();
+        let csr_val: u32 = self.with_clock(|| regs.csr.get());
         let mut ret_val = false;
         if (csr_val & (1 << 1)) == (1 << 1) {
             // tx is ready
@@ -421,6 +495,10 @@ impl USART {
 
 impl dma::DMAClient for USART {
     fn xfer_done(&self, pid: dma::DMAPeripheral) {
+        // Completing a transfer may drop the last reference on the clock
+        // while there are still registers to write
+        self.acquire_clock();
+
         match self.usart_mode.get() {
             UsartMode::Uart => {
 
@@ -431,7 +509,6 @@ impl dma::DMAClient for USART {
                     // disable RX and RX interrupts
                     self.disable_rx();
                     self.disable_rx_interrupts();
-                    self.usart_rx_state.set(USARTStateRX::Idle);
 
                     // get buffer
                     let buffer = self.rx_dma.get().map_or(None, |rx_dma| {
@@ -460,7 +537,7 @@ impl dma::DMAClient for USART {
                     // TX transfer was completed
 
                     // note that the DMA has finished but TX cannot be disabled yet
-                    self.usart_tx_state.set(USARTStateTX::Transfer_Completing);
+                    self.complete_tx();
 
                     // get buffer
                     let buffer = self.tx_dma.get().map_or(None, |tx_dma| {
@@ -496,8 +573,8 @@ impl dma::DMAClient for USART {
                     });
 
                     // note that the DMA has finished but TX cannot be disabled yet
-                    self.usart_tx_state.set(USARTStateTX::Transfer_Completing);
-                    self.usart_rx_state.set(USARTStateRX::Idle);
+                    self.complete_tx();
+                    self.disable_rx();
 
                     // get buffer
                     let txbuf = self.tx_dma.get().map_or(None, |dma| {
@@ -529,6 +606,8 @@ impl dma::DMAClient for USART {
 
             _ => {}
         }
+
+        self.release_clock();
     }
 }
 
@@ -544,7 +623,7 @@ impl hil::uart::UART for USART {
 
         // enable USART clock
         //  must do this before writing any registers
-        self.enable_clock();
+        self.acquire_clock();
 
         // disable interrupts
         self.disable_interrupts();
@@ -579,6 +658,9 @@ impl hil::uart::UART for USART {
 
         // Set baud rate
         self.set_baud_rate(params.baud_rate);
+
+        // the clock only needs to run again once there is a transfer
+        self.release_clock();
     }
 
     fn transmit(&self, tx_data: &'static mut [u8], tx_len: usize) {
@@ -586,8 +668,8 @@ impl hil::uart::UART for USART {
         self.abort_tx(hil::uart::Error::RepeatCallError);
 
         // enable TX
+        self.start_tx();
         self.enable_tx();
-        self.usart_tx_state.set(USARTStateTX::DMA_Transmitting);
 
         // set up dma transfer and start transmission
         self.tx_dma.get().map(move |dma| {
@@ -608,9 +690,9 @@ impl hil::uart::UART for USART {
         }
 
         // enable RX
+        self.start_rx();
         self.enable_rx();
         self.enable_rx_error_interrupts();
-        self.usart_rx_state.set(USARTStateRX::DMA_Receiving);
 
         // set up dma transfer and start reception
         self.rx_dma.get().map(move |dma| {
@@ -627,12 +709,12 @@ impl hil::uart::UARTAdvanced for USART {
         self.abort_rx(hil::uart::Error::RepeatCallError);
 
         // enable receive timeout
-        self.enable_rx_timeout(interbyte_timeout);
+        self.with_clock(|| self.enable_rx_timeout(interbyte_timeout));
 
         // enable RX
+        self.start_rx();
         self.enable_rx();
         self.enable_rx_error_interrupts();
-        self.usart_rx_state.set(USARTStateRX::DMA_Receiving);
 
         // set up dma transfer and start reception
         self.rx_dma.get().map(move |dma| {
@@ -651,9 +733,9 @@ impl hil::uart::UARTAdvanced for USART {
         self.enable_rx_terminator(terminator);
 
         // enable RX
+        self.start_rx();
         self.enable_rx();
         self.enable_rx_error_interrupts();
-        self.usart_rx_state.set(USARTStateRX::DMA_Receiving);
 
         // set up dma transfer and start reception
         self.rx_dma.get().map(move |dma| {
@@ -674,7 +756,7 @@ impl hil::spi::SpiMaster for USART {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
 
         self.usart_mode.set(UsartMode::Spi);
-        self.enable_clock();
+        self.acquire_clock();
 
         // Set baud rate, default to 2 MHz.
         self.set_baud_rate(2000000);
@@ -689,6 +771,8 @@ impl hil::spi::SpiMaster for USART {
 
         // Disable transmitter timeguard
         regs.ttgr.set(4);
+
+        self.release_clock();
     }
 
 
@@ -707,6 +791,7 @@ impl hil::spi::SpiMaster for USART {
                         len: usize)
                         -> bool {
 
+        self.start_tx();
         self.enable_tx();
         self.enable_rx();
 
@@ -728,15 +813,13 @@ impl hil::spi::SpiMaster for USART {
 
         // Set up dma transfer and start transmission
         self.tx_dma.get().map(move |dma| {
-            self.usart_tx_state.set(USARTStateTX::DMA_Transmitting);
-            self.usart_rx_state.set(USARTStateRX::Idle);
             dma.enable();
             dma.do_xfer(self.tx_dma_peripheral, write_buffer, count);
         });
 
         read_buffer.map(|rbuf| {
             self.rx_dma.get().map(move |read| {
-                self.usart_rx_state.set(USARTStateRX::DMA_Receiving);
+                self.start_rx();
                 read.enable();
                 read.do_xfer(self.rx_dma_peripheral, rbuf, count);
             });
@@ -747,23 +830,28 @@ impl hil::spi::SpiMaster for USART {
 
     fn write_byte(&self, val: u8) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        regs.cr.set((1 << 4) | (1 << 6));
+        self.with_clock(|| {
+            regs.cr.set((1 << 4) | (1 << 6));
 
-        regs.thr.set(val as u32);
+            regs.thr.set(val as u32);
+            while regs.csr.get() & (1 << 9) == 0 {} // TXEMPTY
+        });
     }
 
     fn read_byte(&self) -> u8 {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        regs.rhr.get() as u8
+        self.with_clock(|| regs.rhr.get() as u8)
     }
 
     fn read_write_byte(&self, val: u8) -> u8 {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        regs.cr.set((1 << 4) | (1 << 6));
+        self.with_clock(|| {
+            regs.cr.set((1 << 4) | (1 << 6));
 
-        regs.thr.set(val as u32);
-        while regs.csr.get() & (1 << 0) == 0 {}
-        regs.rhr.get() as u8
+            regs.thr.set(val as u32);
+            while regs.csr.get() & (1 << 0) == 0 {}
+            regs.rhr.get() as u8
+        })
     }
 
     /// Pass in a None to use the HW chip select pin on the USART (RTS).
@@ -773,7 +861,7 @@ impl hil::spi::SpiMaster for USART {
 
     /// Returns the actual rate set
     fn set_rate(&self, rate: u32) -> u32 {
-        self.set_baud_rate(rate);
+        self.with_clock(|| self.set_baud_rate(rate));
 
         // Calculate what rate will actually be
         let system_frequency = unsafe { pm::get_system_frequency() };
@@ -784,27 +872,29 @@ impl hil::spi::SpiMaster for USART {
     fn get_rate(&self) -> u32 {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
         let system_frequency = unsafe { pm::get_system_frequency() };
-        let cd = regs.brgr.get() & 0xFFFF;
+        let cd = self.with_clock(|| regs.brgr.get()) & 0xFFFF;
         system_frequency / cd
     }
 
     fn set_clock(&self, polarity: hil::spi::ClockPolarity) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        let mode = regs.mr.get();
+        self.with_clock(|| {
+            let mode = regs.mr.get();
 
-        match polarity {
-            hil::spi::ClockPolarity::IdleLow => {
-                regs.mr.set(mode & !(1 << 16));
-            }
-            hil::spi::ClockPolarity::IdleHigh => {
-                regs.mr.set(mode | (1 << 16));
+            match polarity {
+                hil::spi::ClockPolarity::IdleLow => {
+                    regs.mr.set(mode & !(1 << 16));
+                }
+                hil::spi::ClockPolarity::IdleHigh => {
+                    regs.mr.set(mode | (1 << 16));
+                }
             }
-        }
+        });
     }
 
     fn get_clock(&self) -> hil::spi::ClockPolarity {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        let mode = regs.mr.get();
+        let mode = self.with_clock(|| regs.mr.get());
 
         match mode & (1 << 16) {
             0 => hil::spi::ClockPolarity::IdleLow,
@@ -814,21 +904,23 @@ impl hil::spi::SpiMaster for USART {
 
     fn set_phase(&self, phase: hil::spi::ClockPhase) {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        let mode = regs.mr.get();
+        self.with_clock(|| {
+            let mode = regs.mr.get();
 
-        match phase {
-            hil::spi::ClockPhase::SampleLeading => {
-                regs.mr.set(mode | (1 << 8));
-            }
-            hil::spi::ClockPhase::SampleTrailing => {
-                regs.mr.set(mode & !(1 << 8));
+            match phase {
+                hil::spi::ClockPhase::SampleLeading => {
+                    regs.mr.set(mode | (1 << 8));
+                }
+                hil::spi::ClockPhase::SampleTrailing => {
+                    regs.mr.set(mode & !(1 << 8));
+                }
             }
-        }
+        });
     }
 
     fn get_phase(&self) -> hil::spi::ClockPhase {
         let regs: &mut USARTRegisters = unsafe { mem::transmute(self.registers) };
-        let mode = regs.mr.get();
+        let mode = self.with_clock(|| regs.mr.get());
 
         match mode & (1 << 8) {
             0 => hil::spi::ClockPhase::SampleLeading,
@@ -52,3 +52,51 @@ impl<'a, T: ListNode<'a, T>> List<'a, T> {
         ListIterator { cur: self.head.0.get() }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{List, ListLink, ListNode};
+
+    struct Node<'a> {
+        value: usize,
+        next: ListLink<'a, Node<'a>>,
+    }
+
+    impl<'a> Node<'a> {
+        fn new(value: usize) -> Node<'a> {
+            Node {
+                value: value,
+                next: ListLink::empty(),
+            }
+        }
+    }
+
+    impl<'a> ListNode<'a, Node<'a>> for Node<'a> {
+        fn next(&'a self) -> &'a ListLink<'a, Node<'a>> {
+            &self.next
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let list: List<Node> = List::new();
+        assert!(list.head().is_none());
+        assert_eq!(list.iter().count(), 0);
+    }
+
+    #[test]
+    fn push_head_iterates_newest_first() {
+        let first = Node::new(1);
+        let second = Node::new(2);
+        let third = Node::new(3);
+        let list = List::new();
+        list.push_head(&first);
+        assert_eq!(list.head().map(|node| node.value), Some(1));
+        list.push_head(&second);
+        list.push_head(&third);
+
+        let values: Vec<usize> = list.iter().map(|node| node.value).collect();
+        assert_eq!(values, vec![3, 2, 1]);
+        assert_eq!(list.head().map(|node| node.value), Some(3));
+    }
+}
@@ -5,10 +5,18 @@
 
 use core::intrinsics as int;
 
+#[cfg(target_os = "none")]
 extern "C" {
     fn __errno() -> &mut i32;
 }
 
+// glibc's name for the same function, for builds on a Linux host
+#[cfg(not(target_os = "none"))]
+extern "C" {
+    #[link_name = "__errno_location"]
+    fn __errno() -> &mut i32;
+}
+
 pub fn powf32(base: f32, exponent: f32) -> f32 {
     unsafe { int::powf32(base, exponent) }
 }
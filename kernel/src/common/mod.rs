@@ -7,6 +7,8 @@ pub mod take_cell;
 pub mod volatile_cell;
 pub mod list;
 pub mod math;
+pub mod p256;
+pub mod sha256;
 
 pub use self::list::{List, ListLink, ListNode};
 pub use self::queue::Queue;
@@ -0,0 +1,593 @@
+//! ECDSA signatures over the NIST P-256 curve.
+//!
+//! This is a small, portable implementation meant for checking a handful of
+//! signatures at boot. It is not constant time, which is fine for
+//! verification since all of its inputs are public. Signing, with
+//! deterministic nonces (RFC 6979), is only used by elf2tbf, which builds
+//! this file too, and should only be done on trusted machines. It is left out
+//! of the kernel unless the `sign` feature is enabled, as it is for elf2tbf.
+//!
+//! Numbers are 256-bit integers stored as eight 32-bit limbs, least
+//! significant limb first. Field and scalar arithmetic use Montgomery
+//! multiplication with R = 2^256.
+
+#[cfg(any(test, feature = "sign"))]
+use common::sha256::Sha256;
+
+/// Length in bytes of an uncompressed public key: the big-endian X and Y
+/// coordinates of the point.
+pub const PUBLIC_KEY_LEN: usize = 64;
+
+/// Length in bytes of a signature: the big-endian `r` and `s` values.
+pub const SIGNATURE_LEN: usize = 64;
+
+type U256 = [u32; 8];
+
+struct Modulus {
+    m: U256,
+    /// -m^-1 mod 2^32
+    m_inv: u32,
+    /// R^2 mod m
+    r2: U256,
+}
+
+/// The field prime, 2^256 - 2^224 + 2^192 + 2^96 - 1.
+const P: Modulus = Modulus {
+    m: [0xffffffff, 0xffffffff, 0xffffffff, 0x00000000, 0x00000000, 0x00000000, 0x00000001,
+        0xffffffff],
+    m_inv: 0x00000001,
+    r2: [0x00000003, 0x00000000, 0xffffffff, 0xfffffffb, 0xfffffffe, 0xffffffff, 0xfffffffd,
+         0x00000004],
+};
+
+/// The order of the base point.
+const N: Modulus = Modulus {
+    m: [0xfc632551, 0xf3b9cac2, 0xa7179e84, 0xbce6faad, 0xffffffff, 0xffffffff, 0x00000000,
+        0xffffffff],
+    m_inv: 0xee00bc4f,
+    r2: [0xbe79eea2, 0x83244c95, 0x49bd6fa6, 0x4699799c, 0x2b6bec59, 0x2845b239, 0xf3d95620,
+         0x66e12d94],
+};
+
+/// The curve is y^2 = x^3 - 3x + B.
+const B: U256 = [0x27d2604b, 0x3bce3c3e, 0xcc53b0f6, 0x651d06b0, 0x769886bc, 0xb3ebbd55,
+                 0xaa3a93e7, 0x5ac635d8];
+
+const GX: U256 = [0xd898c296, 0xf4a13945, 0x2deb33a0, 0x77037d81, 0x63a440f2, 0xf8bce6e5,
+                  0xe12c4247, 0x6b17d1f2];
+
+const GY: U256 = [0x37bf51f5, 0xcbb64068, 0x6b315ece, 0x2bce3357, 0x7c0f9e16, 0x8ee7eb4a,
+                  0xfe1a7f9b, 0x4fe342e2];
+
+const ZERO: U256 = [0; 8];
+const ONE: U256 = [1, 0, 0, 0, 0, 0, 0, 0];
+
+fn from_be_bytes(bytes: &[u8]) -> U256 {
+    let mut a = ZERO;
+    for i in 0..8 {
+        let b = &bytes[28 - 4 * i..32 - 4 * i];
+        a[i] = (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32;
+    }
+    a
+}
+
+fn is_zero(a: &U256) -> bool {
+    a.iter().all(|&limb| limb == 0)
+}
+
+/// Returns true if a < b.
+fn less_than(a: &U256, b: &U256) -> bool {
+    for i in (0..8).rev() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+    }
+    false
+}
+
+/// Returns a - b and whether it borrowed.
+fn sub(a: &U256, b: &U256) -> (U256, bool) {
+    let mut r = ZERO;
+    let mut borrow = 0u64;
+    for i in 0..8 {
+        let d = (a[i] as u64).wrapping_sub(b[i] as u64).wrapping_sub(borrow);
+        r[i] = d as u32;
+        borrow = (d >> 63) & 1;
+    }
+    (r, borrow != 0)
+}
+
+/// Returns a + b and whether it carried.
+fn add(a: &U256, b: &U256) -> (U256, bool) {
+    let mut r = ZERO;
+    let mut carry = 0u64;
+    for i in 0..8 {
+        let s = a[i] as u64 + b[i] as u64 + carry;
+        r[i] = s as u32;
+        carry = s >> 32;
+    }
+    (r, carry != 0)
+}
+
+fn add_mod(a: &U256, b: &U256, m: &Modulus) -> U256 {
+    let (s, carry) = add(a, b);
+    if carry || !less_than(&s, &m.m) {
+        sub(&s, &m.m).0
+    } else {
+        s
+    }
+}
+
+fn sub_mod(a: &U256, b: &U256, m: &Modulus) -> U256 {
+    let (d, borrow) = sub(a, b);
+    if borrow { add(&d, &m.m).0 } else { d }
+}
+
+/// Returns a * b / R mod m.
+fn mont_mul(a: &U256, b: &U256, m: &Modulus) -> U256 {
+    let mut t = [0u32; 10];
+    for i in 0..8 {
+        let mut carry = 0u64;
+        for j in 0..8 {
+            let s = t[j] as u64 + (a[j] as u64) * (b[i] as u64) + carry;
+            t[j] = s as u32;
+            carry = s >> 32;
+        }
+        let s = t[8] as u64 + carry;
+        t[8] = s as u32;
+        t[9] = (s >> 32) as u32;
+
+        let u = t[0].wrapping_mul(m.m_inv);
+        let s = t[0] as u64 + (u as u64) * (m.m[0] as u64);
+        let mut carry = s >> 32;
+        for j in 1..8 {
+            let s = t[j] as u64 + (u as u64) * (m.m[j] as u64) + carry;
+            t[j - 1] = s as u32;
+            carry = s >> 32;
+        }
+        let s = t[8] as u64 + carry;
+        t[7] = s as u32;
+        t[8] = t[9] + (s >> 32) as u32;
+    }
+
+    let mut r = ZERO;
+    r.copy_from_slice(&t[..8]);
+    if t[8] != 0 || !less_than(&r, &m.m) {
+        r = sub(&r, &m.m).0;
+    }
+    r
+}
+
+fn to_mont(a: &U256, m: &Modulus) -> U256 {
+    mont_mul(a, &m.r2, m)
+}
+
+fn from_mont(a: &U256, m: &Modulus) -> U256 {
+    mont_mul(a, &ONE, m)
+}
+
+/// Returns a^-1 in Montgomery form, computed as a^(m - 2).
+fn mont_inv(a: &U256, m: &Modulus) -> U256 {
+    let e = sub(&m.m, &[2, 0, 0, 0, 0, 0, 0, 0]).0;
+    let mut r = to_mont(&ONE, m);
+    for i in (0..256).rev() {
+        r = mont_mul(&r, &r, m);
+        if (e[i / 32] >> (i % 32)) & 1 == 1 {
+            r = mont_mul(&r, a, m);
+        }
+    }
+    r
+}
+
+/// A point in Jacobian coordinates, with field elements in Montgomery form.
+/// Z = 0 is the point at infinity.
+#[derive(Clone, Copy)]
+struct Point {
+    x: U256,
+    y: U256,
+    z: U256,
+}
+
+impl Point {
+    fn infinity() -> Point {
+        Point {
+            x: ZERO,
+            y: ZERO,
+            z: ZERO,
+        }
+    }
+
+    fn from_affine(x: &U256, y: &U256) -> Point {
+        Point {
+            x: to_mont(x, &P),
+            y: to_mont(y, &P),
+            z: to_mont(&ONE, &P),
+        }
+    }
+
+    fn is_infinity(&self) -> bool {
+        is_zero(&self.z)
+    }
+
+    fn double(&self) -> Point {
+        if self.is_infinity() || is_zero(&self.y) {
+            return Point::infinity();
+        }
+        let mul = |a: &U256, b: &U256| mont_mul(a, b, &P);
+        let add = |a: &U256, b: &U256| add_mod(a, b, &P);
+        let sub = |a: &U256, b: &U256| sub_mod(a, b, &P);
+
+        let delta = mul(&self.z, &self.z);
+        let gamma = mul(&self.y, &self.y);
+        let beta = mul(&self.x, &gamma);
+        let t = mul(&sub(&self.x, &delta), &add(&self.x, &delta));
+        let alpha = add(&add(&t, &t), &t);
+        let beta2 = add(&beta, &beta);
+        let beta4 = add(&beta2, &beta2);
+        let beta8 = add(&beta4, &beta4);
+        let x3 = sub(&mul(&alpha, &alpha), &beta8);
+        let yz = add(&self.y, &self.z);
+        let z3 = sub(&sub(&mul(&yz, &yz), &gamma), &delta);
+        let gamma2 = mul(&gamma, &gamma);
+        let gamma2_2 = add(&gamma2, &gamma2);
+        let gamma2_4 = add(&gamma2_2, &gamma2_2);
+        let gamma2_8 = add(&gamma2_4, &gamma2_4);
+        let y3 = sub(&mul(&alpha, &sub(&beta4, &x3)), &gamma2_8);
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    fn add(&self, other: &Point) -> Point {
+        if self.is_infinity() {
+            return *other;
+        }
+        if other.is_infinity() {
+            return *self;
+        }
+        let mul = |a: &U256, b: &U256| mont_mul(a, b, &P);
+        let sub = |a: &U256, b: &U256| sub_mod(a, b, &P);
+
+        let z1z1 = mul(&self.z, &self.z);
+        let z2z2 = mul(&other.z, &other.z);
+        let u1 = mul(&self.x, &z2z2);
+        let u2 = mul(&other.x, &z1z1);
+        let s1 = mul(&mul(&self.y, &other.z), &z2z2);
+        let s2 = mul(&mul(&other.y, &self.z), &z1z1);
+        if u1 == u2 {
+            return if s1 == s2 {
+                self.double()
+            } else {
+                Point::infinity()
+            };
+        }
+
+        let h = sub(&u2, &u1);
+        let r = sub(&s2, &s1);
+        let hh = mul(&h, &h);
+        let hhh = mul(&h, &hh);
+        let v = mul(&u1, &hh);
+        let x3 = sub(&sub(&sub(&mul(&r, &r), &hhh), &v), &v);
+        let y3 = sub(&mul(&r, &sub(&v, &x3)), &mul(&s1, &hhh));
+        let z3 = mul(&mul(&self.z, &other.z), &h);
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// Returns the affine X coordinate, in normal form.
+    fn affine_x(&self) -> U256 {
+        let z_inv = mont_inv(&self.z, &P);
+        let z_inv2 = mont_mul(&z_inv, &z_inv, &P);
+        from_mont(&mont_mul(&self.x, &z_inv2, &P), &P)
+    }
+}
+
+/// Returns true if (x, y) is a point on the curve.
+fn on_curve(x: &U256, y: &U256) -> bool {
+    if !less_than(x, &P.m) || !less_than(y, &P.m) {
+        return false;
+    }
+    let x = to_mont(x, &P);
+    let y = to_mont(y, &P);
+    let y2 = mont_mul(&y, &y, &P);
+    let x3 = mont_mul(&mont_mul(&x, &x, &P), &x, &P);
+    let three_x = add_mod(&add_mod(&x, &x, &P), &x, &P);
+    let rhs = add_mod(&sub_mod(&x3, &three_x, &P), &to_mont(&B, &P), &P);
+    y2 == rhs
+}
+
+/// Checks an ECDSA signature of a SHA-256 `digest` against `public_key`.
+pub fn verify(public_key: &[u8; PUBLIC_KEY_LEN],
+              digest: &[u8; 32],
+              signature: &[u8; SIGNATURE_LEN])
+              -> bool {
+    let qx = from_be_bytes(&public_key[..32]);
+    let qy = from_be_bytes(&public_key[32..]);
+    if !on_curve(&qx, &qy) {
+        return false;
+    }
+
+    let r = from_be_bytes(&signature[..32]);
+    let s = from_be_bytes(&signature[32..]);
+    if is_zero(&r) || is_zero(&s) || !less_than(&r, &N.m) || !less_than(&s, &N.m) {
+        return false;
+    }
+
+    // The digest is less than 2^256 < 2n, so one subtraction reduces it
+    let mut e = from_be_bytes(digest);
+    if !less_than(&e, &N.m) {
+        e = sub(&e, &N.m).0;
+    }
+
+    // u1 = e / s and u2 = r / s, both mod n
+    let w = mont_inv(&to_mont(&s, &N), &N);
+    let u1 = from_mont(&mont_mul(&to_mont(&e, &N), &w, &N), &N);
+    let u2 = from_mont(&mont_mul(&to_mont(&r, &N), &w, &N), &N);
+
+    // u1 * G + u2 * Q, processing both scalars together
+    let g = Point::from_affine(&GX, &GY);
+    let q = Point::from_affine(&qx, &qy);
+    let gq = g.add(&q);
+    let mut sum = Point::infinity();
+    for i in (0..256).rev() {
+        sum = sum.double();
+        let bit1 = (u1[i / 32] >> (i % 32)) & 1 == 1;
+        let bit2 = (u2[i / 32] >> (i % 32)) & 1 == 1;
+        sum = match (bit1, bit2) {
+            (true, true) => sum.add(&gq),
+            (true, false) => sum.add(&g),
+            (false, true) => sum.add(&q),
+            (false, false) => sum,
+        };
+    }
+    if sum.is_infinity() {
+        return false;
+    }
+
+    // Accept if x(sum) mod n == r. x is less than p < 2n.
+    let mut x = sum.affine_x();
+    if !less_than(&x, &N.m) {
+        x = sub(&x, &N.m).0;
+    }
+    x == r
+}
+
+/// Length in bytes of a private key: the big-endian secret scalar.
+#[cfg(any(test, feature = "sign"))]
+pub const PRIVATE_KEY_LEN: usize = 32;
+
+#[cfg(any(test, feature = "sign"))]
+fn to_be_bytes(a: &U256, bytes: &mut [u8]) {
+    for i in 0..8 {
+        bytes[28 - 4 * i] = (a[i] >> 24) as u8;
+        bytes[29 - 4 * i] = (a[i] >> 16) as u8;
+        bytes[30 - 4 * i] = (a[i] >> 8) as u8;
+        bytes[31 - 4 * i] = a[i] as u8;
+    }
+}
+
+/// Returns k * G.
+#[cfg(any(test, feature = "sign"))]
+fn mul_base(k: &U256) -> Point {
+    let g = Point::from_affine(&GX, &GY);
+    let mut sum = Point::infinity();
+    for i in (0..256).rev() {
+        sum = sum.double();
+        if (k[i / 32] >> (i % 32)) & 1 == 1 {
+            sum = sum.add(&g);
+        }
+    }
+    sum
+}
+
+#[cfg(any(test, feature = "sign"))]
+fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut inner = Sha256::new();
+    let mut outer = Sha256::new();
+    let mut ipad = [0x36; 64];
+    let mut opad = [0x5c; 64];
+    for (i, byte) in key.iter().enumerate() {
+        ipad[i] ^= *byte;
+        opad[i] ^= *byte;
+    }
+    inner.update(&ipad);
+    for part in parts {
+        inner.update(part);
+    }
+    outer.update(&opad);
+    outer.update(&inner.finish());
+    outer.finish()
+}
+
+/// Returns the public key for `private_key`, or `None` if the key is not a
+/// valid scalar.
+#[cfg(any(test, feature = "sign"))]
+pub fn public_key(private_key: &[u8; PRIVATE_KEY_LEN]) -> Option<[u8; PUBLIC_KEY_LEN]> {
+    let d = from_be_bytes(private_key);
+    if is_zero(&d) || !less_than(&d, &N.m) {
+        return None;
+    }
+    let q = mul_base(&d);
+    let z_inv = mont_inv(&q.z, &P);
+    let z_inv2 = mont_mul(&z_inv, &z_inv, &P);
+    let z_inv3 = mont_mul(&z_inv2, &z_inv, &P);
+    let x = from_mont(&mont_mul(&q.x, &z_inv2, &P), &P);
+    let y = from_mont(&mont_mul(&q.y, &z_inv3, &P), &P);
+
+    let mut key = [0; PUBLIC_KEY_LEN];
+    to_be_bytes(&x, &mut key[..32]);
+    to_be_bytes(&y, &mut key[32..]);
+    Some(key)
+}
+
+/// Signs a SHA-256 `digest`, choosing the nonce as described in RFC 6979.
+/// `private_key` must be valid (see `public_key`).
+#[cfg(any(test, feature = "sign"))]
+pub fn sign(private_key: &[u8; PRIVATE_KEY_LEN], digest: &[u8; 32]) -> [u8; SIGNATURE_LEN] {
+    let d = from_be_bytes(private_key);
+    let mut e = from_be_bytes(digest);
+    if !less_than(&e, &N.m) {
+        e = sub(&e, &N.m).0;
+    }
+    let mut h1 = [0; 32];
+    to_be_bytes(&e, &mut h1);
+
+    let mut v = [0x01; 32];
+    let mut k = [0x00; 32];
+    k = hmac_sha256(&k, &[&v[..], &[0x00], &private_key[..], &h1[..]]);
+    v = hmac_sha256(&k, &[&v[..]]);
+    k = hmac_sha256(&k, &[&v[..], &[0x01], &private_key[..], &h1[..]]);
+    v = hmac_sha256(&k, &[&v[..]]);
+
+    loop {
+        v = hmac_sha256(&k, &[&v[..]]);
+        let nonce = from_be_bytes(&v);
+        if !is_zero(&nonce) && less_than(&nonce, &N.m) {
+            let mut r = mul_base(&nonce).affine_x();
+            if !less_than(&r, &N.m) {
+                r = sub(&r, &N.m).0;
+            }
+
+            // s = (e + r * d) / nonce mod n
+            let rd = mont_mul(&to_mont(&r, &N), &to_mont(&d, &N), &N);
+            let sum = add_mod(&to_mont(&e, &N), &rd, &N);
+            let nonce_inv = mont_inv(&to_mont(&nonce, &N), &N);
+            let s = from_mont(&mont_mul(&sum, &nonce_inv, &N), &N);
+
+            if !is_zero(&r) && !is_zero(&s) {
+                let mut signature = [0; SIGNATURE_LEN];
+                to_be_bytes(&r, &mut signature[..32]);
+                to_be_bytes(&s, &mut signature[32..]);
+                return signature;
+            }
+        }
+        k = hmac_sha256(&k, &[&v[..], &[0x00]]);
+        v = hmac_sha256(&k, &[&v[..]]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::sha256::Sha256;
+    use super::{PRIVATE_KEY_LEN, PUBLIC_KEY_LEN, SIGNATURE_LEN, public_key, sign, verify};
+
+    // Key and signatures from RFC 6979, appendix A.2.5
+    const PRIVATE_KEY: &'static str =
+        "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+    const PUBLIC_KEY: &'static str =
+        "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6\
+         7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299";
+    const SAMPLE_SIGNATURE: &'static str =
+        "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716\
+         f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8";
+    const TEST_SIGNATURE: &'static str =
+        "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367\
+         019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083";
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len() / 2).map(|i| u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap()).collect()
+    }
+
+    fn private_key() -> [u8; PRIVATE_KEY_LEN] {
+        let mut key = [0; PRIVATE_KEY_LEN];
+        key.copy_from_slice(&hex(PRIVATE_KEY));
+        key
+    }
+
+    fn public_key_bytes() -> [u8; PUBLIC_KEY_LEN] {
+        let mut key = [0; PUBLIC_KEY_LEN];
+        key.copy_from_slice(&hex(PUBLIC_KEY));
+        key
+    }
+
+    fn signature(s: &str) -> [u8; SIGNATURE_LEN] {
+        let mut signature = [0; SIGNATURE_LEN];
+        signature.copy_from_slice(&hex(s));
+        signature
+    }
+
+    fn digest(message: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(message);
+        hasher.finish()
+    }
+
+    #[test]
+    fn derives_public_key() {
+        assert_eq!(&public_key(&private_key()).unwrap()[..], &public_key_bytes()[..]);
+    }
+
+    #[test]
+    fn rejects_invalid_private_keys() {
+        assert!(public_key(&[0; PRIVATE_KEY_LEN]).is_none());
+        assert!(public_key(&[0xff; PRIVATE_KEY_LEN]).is_none());
+    }
+
+    #[test]
+    fn signs_deterministically() {
+        assert_eq!(&sign(&private_key(), &digest(b"sample"))[..],
+                   &signature(SAMPLE_SIGNATURE)[..]);
+        assert_eq!(&sign(&private_key(), &digest(b"test"))[..],
+                   &signature(TEST_SIGNATURE)[..]);
+    }
+
+    #[test]
+    fn verifies_valid_signatures() {
+        let key = public_key_bytes();
+        assert!(verify(&key, &digest(b"sample"), &signature(SAMPLE_SIGNATURE)));
+        assert!(verify(&key, &digest(b"test"), &signature(TEST_SIGNATURE)));
+    }
+
+    #[test]
+    fn rejects_wrong_digest() {
+        let key = public_key_bytes();
+        assert!(!verify(&key, &digest(b"test"), &signature(SAMPLE_SIGNATURE)));
+        assert!(!verify(&key, &digest(b"sample"), &signature(TEST_SIGNATURE)));
+    }
+
+    #[test]
+    fn rejects_modified_signature() {
+        let key = public_key_bytes();
+        let message = digest(b"sample");
+        for &i in [0, 31, 32, 63].iter() {
+            let mut modified = signature(SAMPLE_SIGNATURE);
+            modified[i] ^= 1;
+            assert!(!verify(&key, &message, &modified));
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_r_and_s() {
+        let key = public_key_bytes();
+        let message = digest(b"sample");
+        let order = hex("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551");
+
+        // r or s of zero, of the group order or above it
+        for &(start, end) in [(0, 32), (32, 64)].iter() {
+            for value in [[0; 32].to_vec(), order.clone(), [0xff; 32].to_vec()].iter() {
+                let mut bad = signature(SAMPLE_SIGNATURE);
+                bad[start..end].copy_from_slice(value);
+                assert!(!verify(&key, &message, &bad));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_public_key_not_on_curve() {
+        let mut key = public_key_bytes();
+        key[63] ^= 1;
+        assert!(!verify(&key, &digest(b"sample"), &signature(SAMPLE_SIGNATURE)));
+
+        // Coordinates must be reduced modulo the field prime
+        let mut key = public_key_bytes();
+        for byte in key[..32].iter_mut() {
+            *byte = 0xff;
+        }
+        assert!(!verify(&key, &digest(b"sample"), &signature(SAMPLE_SIGNATURE)));
+    }
+}
@@ -15,6 +15,20 @@ impl<'a, T: Copy> RingBuffer<'a, T> {
             ring: ring,
         }
     }
+
+    /// Calls `f` on each queued element, oldest first, without removing
+    /// any of them.
+    pub fn for_each<F>(&self, mut f: F)
+        where F: FnMut(T)
+    {
+        let head = unsafe { read_volatile(&self.head) };
+        let tail = unsafe { read_volatile(&self.tail) };
+        let mut i = head;
+        while i != tail {
+            f(self.ring[i]);
+            i = (i + 1) % self.ring.len();
+        }
+    }
 }
 
 impl<'a, T: Copy> queue::Queue<T> for RingBuffer<'a, T> {
@@ -68,3 +82,112 @@ impl<'a, T: Copy> queue::Queue<T> for RingBuffer<'a, T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::queue::Queue;
+    use super::RingBuffer;
+
+    #[test]
+    fn empty() {
+        let mut ring = [0; 4];
+        let mut buffer = RingBuffer::new(&mut ring);
+        assert!(!buffer.has_elements());
+        assert!(!buffer.is_full());
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.dequeue(), None);
+    }
+
+    #[test]
+    fn fifo_order() {
+        let mut ring = [0; 4];
+        let mut buffer = RingBuffer::new(&mut ring);
+        assert!(buffer.enqueue(1));
+        assert!(buffer.enqueue(2));
+        assert!(buffer.has_elements());
+        assert_eq!(buffer.dequeue(), Some(1));
+        assert_eq!(buffer.dequeue(), Some(2));
+        assert_eq!(buffer.dequeue(), None);
+    }
+
+    #[test]
+    fn holds_one_less_than_its_length() {
+        let mut ring = [0; 4];
+        let mut buffer = RingBuffer::new(&mut ring);
+        assert!(buffer.enqueue(1));
+        assert!(buffer.enqueue(2));
+        assert!(!buffer.is_full());
+        assert!(buffer.enqueue(3));
+        assert!(buffer.is_full());
+        assert_eq!(buffer.len(), 3);
+
+        // A full buffer rejects new elements and keeps the old ones
+        assert!(!buffer.enqueue(4));
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.dequeue(), Some(1));
+        assert!(!buffer.is_full());
+        assert!(buffer.enqueue(4));
+        assert!(buffer.is_full());
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut ring = [0; 4];
+        let mut buffer = RingBuffer::new(&mut ring);
+        assert!(buffer.enqueue(1));
+        assert!(buffer.enqueue(2));
+        assert!(buffer.enqueue(3));
+        assert_eq!(buffer.dequeue(), Some(1));
+        assert_eq!(buffer.dequeue(), Some(2));
+
+        // The tail wraps past the end of the slice, behind the head
+        assert!(buffer.enqueue(4));
+        assert!(buffer.enqueue(5));
+        assert_eq!(buffer.len(), 3);
+        assert!(buffer.is_full());
+
+        assert_eq!(buffer.dequeue(), Some(3));
+        assert_eq!(buffer.dequeue(), Some(4));
+        assert_eq!(buffer.dequeue(), Some(5));
+        assert_eq!(buffer.len(), 0);
+        assert!(!buffer.has_elements());
+    }
+
+    #[test]
+    fn for_each_visits_in_order_without_removing() {
+        let mut ring = [0; 4];
+        let mut buffer = RingBuffer::new(&mut ring);
+        assert!(buffer.enqueue(1));
+        assert!(buffer.enqueue(2));
+        assert!(buffer.enqueue(3));
+        assert_eq!(buffer.dequeue(), Some(1));
+        assert!(buffer.enqueue(4));
+
+        let mut seen = [0; 3];
+        let mut count = 0;
+        buffer.for_each(|val| {
+            seen[count] = val;
+            count += 1;
+        });
+        assert_eq!(count, 3);
+        assert_eq!(seen, [2, 3, 4]);
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.dequeue(), Some(2));
+    }
+
+    #[test]
+    fn len_across_many_wraps() {
+        let mut ring = [0; 3];
+        let mut buffer = RingBuffer::new(&mut ring);
+        for i in 0..10 {
+            assert!(buffer.enqueue(i));
+            assert_eq!(buffer.len(), 1);
+            assert!(buffer.enqueue(i + 100));
+            assert_eq!(buffer.len(), 2);
+            assert!(buffer.is_full());
+            assert_eq!(buffer.dequeue(), Some(i));
+            assert_eq!(buffer.dequeue(), Some(i + 100));
+            assert_eq!(buffer.len(), 0);
+        }
+    }
+}
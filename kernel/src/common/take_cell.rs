@@ -39,7 +39,9 @@ impl<'a, T: ?Sized> TakeCell<'a, T> {
     /// # Examples
     ///
     /// ```
-    /// let cell = TakeCell::new(1234);
+    /// # use kernel::common::take_cell::TakeCell;
+    /// let mut value = 1234;
+    /// let cell = TakeCell::new(&mut value);
     /// let x = &cell;
     /// let y = &cell;
     ///
@@ -80,18 +82,20 @@ impl<'a, T: ?Sized> TakeCell<'a, T> {
     /// # Examples
     ///
     /// ```
-    /// let cell = TakeCell::new(1234);
+    /// # use kernel::common::take_cell::TakeCell;
+    /// let mut value = 1234;
+    /// let cell = TakeCell::new(&mut value);
     /// let x = &cell;
     /// let y = &cell;
     ///
     /// x.map(|value| {
     ///     // We have mutable access to the value while in the closure
-    ///     value += 1;
+    ///     *value += 1;
     /// });
     ///
     /// // After the closure completes, the mutable memory is still in the cell,
     /// // but potentially changed.
-    /// assert_eq!(y.take(), Some(1235));
+    /// assert_eq!(y.take(), Some(&mut 1235));
     /// ```
     pub fn map<F, R>(&self, closure: F) -> Option<R>
         where F: FnOnce(&mut T) -> R
@@ -178,6 +182,7 @@ impl<T> MapCell<T> {
     /// # Examples
     ///
     /// ```
+    /// # use kernel::common::take_cell::MapCell;
     /// let cell = MapCell::new(1234);
     /// let x = &cell;
     /// let y = &cell;
@@ -219,13 +224,14 @@ impl<T> MapCell<T> {
     /// # Examples
     ///
     /// ```
+    /// # use kernel::common::take_cell::MapCell;
     /// let cell = MapCell::new(1234);
     /// let x = &cell;
     /// let y = &cell;
     ///
     /// x.map(|value| {
     ///     // We have mutable access to the value while in the closure
-    ///     value += 1;
+    ///     *value += 1;
     /// });
     ///
     /// // After the closure completes, the mutable memory is still in the cell,
@@ -261,3 +267,146 @@ impl<T> MapCell<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::{MapCell, TakeCell};
+
+    #[test]
+    fn take_cell_empty() {
+        let cell: TakeCell<u32> = TakeCell::empty();
+        assert!(cell.is_none());
+        assert_eq!(cell.take(), None);
+        assert_eq!(cell.map(|value| *value), None);
+        assert_eq!(cell.map_or(7, |value| *value), 7);
+    }
+
+    #[test]
+    fn take_cell_take_leaves_it_empty() {
+        let mut value = 1;
+        let cell = TakeCell::new(&mut value);
+        assert!(cell.is_some());
+        assert_eq!(cell.take(), Some(&mut 1));
+        assert!(cell.is_none());
+        assert_eq!(cell.take(), None);
+    }
+
+    #[test]
+    fn take_cell_replace_and_put() {
+        let mut first = 1;
+        let mut second = 2;
+        let cell = TakeCell::empty();
+        assert_eq!(cell.replace(&mut first), None);
+        assert_eq!(cell.replace(&mut second), Some(&mut 1));
+        cell.put(None);
+        assert!(cell.is_none());
+    }
+
+    #[test]
+    fn take_cell_map_keeps_the_value() {
+        let mut value = 1;
+        let cell = TakeCell::new(&mut value);
+        let result = cell.map(|value| {
+            *value += 1;
+            *value
+        });
+        assert_eq!(result, Some(2));
+        assert_eq!(cell.map_or(0, |value| *value), 2);
+        assert_eq!(cell.take(), Some(&mut 2));
+    }
+
+    #[test]
+    fn take_cell_modify_or_replace_modifies_a_full_cell() {
+        let mut value = 1;
+        let mut spare = 10;
+        let spare = &mut spare;
+        let made = Cell::new(false);
+        let cell = TakeCell::new(&mut value);
+        cell.modify_or_replace(|value| *value += 1, || {
+            made.set(true);
+            spare
+        });
+        assert!(!made.get());
+        assert_eq!(cell.take(), Some(&mut 2));
+    }
+
+    #[test]
+    fn take_cell_modify_or_replace_fills_an_empty_cell() {
+        let mut value = 10;
+        let value = &mut value;
+        let modified = Cell::new(false);
+        let cell = TakeCell::empty();
+        cell.modify_or_replace(|_| modified.set(true), || value);
+        assert!(!modified.get());
+        assert_eq!(cell.take(), Some(&mut 10));
+    }
+
+    #[test]
+    fn map_cell_empty() {
+        let cell: MapCell<u32> = MapCell::empty();
+        assert!(cell.is_none());
+        assert_eq!(cell.take(), None);
+        assert_eq!(cell.map(|value| *value), None);
+        assert_eq!(cell.map_or(7, |value| *value), 7);
+    }
+
+    #[test]
+    fn map_cell_take_and_put() {
+        let cell = MapCell::new(1);
+        assert_eq!(cell.take(), Some(1));
+        assert!(cell.is_none());
+        assert_eq!(cell.take(), None);
+        cell.put(2);
+        assert!(cell.is_some());
+        assert_eq!(cell.take(), Some(2));
+    }
+
+    #[test]
+    fn map_cell_replace() {
+        let cell = MapCell::empty();
+        assert_eq!(cell.replace(1), None);
+        assert_eq!(cell.replace(2), Some(1));
+        assert_eq!(cell.take(), Some(2));
+    }
+
+    #[test]
+    fn map_cell_map_keeps_the_value() {
+        let cell = MapCell::new(1);
+        let result = cell.map(|value| {
+            *value += 1;
+            *value
+        });
+        assert_eq!(result, Some(2));
+        assert_eq!(cell.take(), Some(2));
+    }
+
+    #[test]
+    fn map_cell_is_empty_inside_map() {
+        let cell = MapCell::new(1);
+        let inner = cell.map(|_| cell.map(|value| *value));
+        assert_eq!(inner, Some(None));
+        assert!(cell.is_some());
+    }
+
+    #[test]
+    fn map_cell_modify_or_replace_modifies_a_full_cell() {
+        let made = Cell::new(false);
+        let cell = MapCell::new(1);
+        cell.modify_or_replace(|value| *value += 1, || {
+            made.set(true);
+            10
+        });
+        assert!(!made.get());
+        assert_eq!(cell.take(), Some(2));
+    }
+
+    #[test]
+    fn map_cell_modify_or_replace_fills_an_empty_cell() {
+        let modified = Cell::new(false);
+        let cell = MapCell::empty();
+        cell.modify_or_replace(|_| modified.set(true), || 10);
+        assert!(!modified.get());
+        assert_eq!(cell.take(), Some(10));
+    }
+}
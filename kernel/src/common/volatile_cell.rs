@@ -21,3 +21,25 @@ impl<T> VolatileCell<T> {
         unsafe { ::core::ptr::write_volatile(&self.value as *const T as *mut T, value) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::VolatileCell;
+
+    #[test]
+    fn get_and_set() {
+        let cell = VolatileCell::new(0x1234u32);
+        assert_eq!(cell.get(), 0x1234);
+        cell.set(0xdead_beef);
+        assert_eq!(cell.get(), 0xdead_beef);
+    }
+
+    #[test]
+    fn copies_are_independent() {
+        let cell = VolatileCell::new(1u8);
+        let copy = cell;
+        cell.set(2);
+        assert_eq!(copy.get(), 1);
+        assert_eq!(cell.get(), 2);
+    }
+}
@@ -2,12 +2,48 @@ use callback::AppId;
 use core::marker::PhantomData;
 use core::mem::size_of;
 use core::ops::{Deref, DerefMut};
-use core::ptr::{read_volatile, write_volatile, Unique};
+use core::ptr::{read_volatile, write, write_volatile, Unique};
 use debug;
 use process::{self, Error};
 
 pub static mut CONTAINER_COUNTER: usize = 0;
 
+/// Notified when a process's containers are reset, because it faulted and is
+/// not running or because it is restarted. Capsules that keep per-app state
+/// outside of their containers, or that count something across apps, use this
+/// to drop pending operations for the app.
+pub trait ResetClient {
+    /// Called before the containers of `appid` are reset. The containers can
+    /// still be read through `Container::container`, but no callbacks should be
+    /// scheduled for the app.
+    fn containers_reset(&self, appid: AppId);
+}
+
+const MAX_RESET_CLIENTS: usize = 16;
+
+static mut RESET_CLIENTS: [Option<&'static ResetClient>; MAX_RESET_CLIENTS] =
+    [None; MAX_RESET_CLIENTS];
+
+/// Registers a client to be notified when a process's containers are reset.
+/// Returns false if too many clients are registered.
+pub unsafe fn add_reset_client(client: &'static ResetClient) -> bool {
+    match RESET_CLIENTS.iter_mut().find(|c| c.is_none()) {
+        Some(slot) => {
+            *slot = Some(client);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Notifies all registered clients that the containers of `appid` are about
+/// to be reset.
+pub unsafe fn notify_reset(appid: AppId) {
+    for client in RESET_CLIENTS.iter() {
+        client.map(|client| client.containers_reset(appid));
+    }
+}
+
 pub struct Container<T: Default> {
     container_num: usize,
     ptr: PhantomData<T>,
@@ -15,6 +51,7 @@ pub struct Container<T: Default> {
 
 pub struct AppliedContainer<T> {
     appid: usize,
+    container_num: usize,
     container: *mut T,
     _phantom: PhantomData<T>,
 }
@@ -34,8 +71,9 @@ impl<T> AppliedContainer<T> {
         let mut allocator = Allocator {
             app: unsafe { Some(process::PROCS[self.appid].as_mut().unwrap()) },
             app_id: self.appid,
+            container_num: self.container_num,
         };
-        let mut root = unsafe { Owned::new(self.container, self.appid) };
+        let mut root = unsafe { Owned::new_root(self.container, self.appid) };
         fun(&mut root, &mut allocator)
     }
 }
@@ -43,11 +81,15 @@ impl<T> AppliedContainer<T> {
 pub struct Allocator<'a> {
     app: Option<&'a mut process::Process<'a>>,
     app_id: usize,
+    container_num: usize,
 }
 
 pub struct Owned<T: ?Sized> {
     data: Unique<T>,
     app_id: usize,
+    /// Whether the memory is freed when dropped. A container's root lives
+    /// until the container is reset.
+    free_on_drop: bool,
 }
 
 impl<T: ?Sized> Owned<T> {
@@ -55,6 +97,15 @@ impl<T: ?Sized> Owned<T> {
         Owned {
             data: Unique::new(data),
             app_id: app_id,
+            free_on_drop: true,
+        }
+    }
+
+    unsafe fn new_root(data: *mut T, app_id: usize) -> Owned<T> {
+        Owned {
+            data: Unique::new(data),
+            app_id: app_id,
+            free_on_drop: false,
         }
     }
 
@@ -68,7 +119,9 @@ impl<T: ?Sized> Drop for Owned<T> {
         unsafe {
             let app_id = self.app_id;
             let data = self.data.get_mut() as *mut T as *mut u8;
-            if AppId::is_kernel_idx(app_id) {
+            if !self.free_on_drop {
+                /* roots are freed when the container is reset */
+            } else if AppId::is_kernel_idx(app_id) {
                 /* kernel free is nop */
 ;
             } else {
@@ -102,10 +155,12 @@ impl<'a> Allocator<'a> {
             let app_id = self.app_id;
             match self.app.as_mut() {
                 Some(app) => {
-                    app.alloc(size_of::<T>()).map_or(Err(Error::OutOfMemory), |arr| {
-                        let mut owned = Owned::new(arr.as_mut_ptr() as *mut T, app_id);
-                        *owned = data;
-                        Ok(owned)
+                    let container_num = self.container_num;
+                    app.alloc(size_of::<T>(), container_num).map_or(Err(Error::OutOfMemory), |arr| {
+                        let ptr = arr.as_mut_ptr() as *mut T;
+                        // The memory is uninitialized, so don't drop its contents
+                        write(ptr, data);
+                        Ok(Owned::new(ptr, app_id))
                     })
                 }
                 None => {
@@ -136,6 +191,7 @@ impl<T: Default> Container<T> {
                 let cntr = kernel_container_for::<T>(app_id);
                 Some(AppliedContainer {
                     appid: app_id,
+                    container_num: self.container_num,
                     container: cntr,
                     _phantom: PhantomData,
                 })
@@ -148,6 +204,7 @@ impl<T: Default> Container<T> {
                         } else {
                             Some(AppliedContainer {
                                 appid: app_id,
+                                container_num: self.container_num,
                                 container: cntr,
                                 _phantom: PhantomData,
                             })
@@ -167,10 +224,11 @@ impl<T: Default> Container<T> {
             let app_id = appid.idx();
             if AppId::is_kernel(appid) {
                 let root_ptr = kernel_container_for::<T>(app_id);
-                let mut root = Owned::new(root_ptr, app_id);
+                let mut root = Owned::new_root(root_ptr, app_id);
                 let mut allocator = Allocator {
                     app: None,
                     app_id: app_id,
+                    container_num: self.container_num,
                 };
                 let res = fun(&mut root, &mut allocator);
                 Ok(res)
@@ -179,10 +237,11 @@ impl<T: Default> Container<T> {
                     Some(ref mut app) => {
                         app.container_for_or_alloc::<T>(self.container_num)
                             .map_or(Err(Error::OutOfMemory), move |root_ptr| {
-                                let mut root = Owned::new(root_ptr, app_id);
+                                let mut root = Owned::new_root(root_ptr, app_id);
                                 let mut allocator = Allocator {
                                     app: Some(app),
                                     app_id: app_id,
+                                    container_num: self.container_num,
                                 };
                                 let res = fun(&mut root, &mut allocator);
                                 Ok(res)
@@ -194,6 +253,16 @@ impl<T: Default> Container<T> {
         }
     }
 
+    /// Bytes of grant memory the container has allocated for `appid`,
+    /// including its root and allocation headers.
+    pub fn grant_usage(&self, appid: AppId) -> usize {
+        if AppId::is_kernel(appid) {
+            return 0;
+        }
+        process::with_process(appid.idx(), |p| p.container_usage(self.container_num))
+            .unwrap_or(0)
+    }
+
     pub fn each<F>(&self, fun: F)
         where F: Fn(&mut Owned<T>)
     {
@@ -202,7 +271,7 @@ impl<T: Default> Container<T> {
             for (app_id, app) in itr.enumerate() {
                 let root_ptr = app.container_for::<T>(self.container_num);
                 if !root_ptr.is_null() {
-                    let mut root = Owned::new(root_ptr, app_id);
+                    let mut root = Owned::new_root(root_ptr, app_id);
                     fun(&mut root);
                 }
             }
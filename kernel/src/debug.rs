@@ -5,6 +5,7 @@ use core::ptr::{read_volatile, write_volatile};
 use core::str;
 use driver::Driver;
 use mem::AppSlice;
+use process;
 use returncode::ReturnCode;
 
 pub const APPID_IDX: usize = 255;
@@ -99,11 +100,13 @@ impl DebugWriter {
                                       end - start,
                                       AppId::kernel_new(APPID_IDX));
                     let slice_len = slice.len();
-                    if driver.allow(AppId::kernel_new(APPID_IDX), 1, slice) != ReturnCode::SUCCESS {
+                    let appid = AppId::kernel_new(APPID_IDX);
+                    if driver.allow(appid, 1, Some(slice)) != ReturnCode::SUCCESS {
                         panic!("Debug print allow fail");
                     }
                     write_volatile(&mut DEBUG_WRITER.output_active_len, slice_len);
-                    if driver.subscribe(1, KERNEL_CONSOLE_CALLBACK) != ReturnCode::SUCCESS {
+                    if driver.subscribe(1, Some(KERNEL_CONSOLE_CALLBACK), appid) !=
+                       ReturnCode::SUCCESS {
                         panic!("Debug print subscribe fail");
                     }
                 }
@@ -171,14 +174,16 @@ impl Write for DebugWriter {
         //  - tail points one past the index of the last open place to write
         //  -> head == tail implies buffer is empty
         //  -> there's no "full/empty" bit, so the effective buffer size is -1
+        //
+        // Bytes that do not fit in the free space are dropped rather than
+        // overwriting output that has not been published yet.
 
         let mut head = unsafe { read_volatile(&DEBUG_WRITER.output_head) };
         let tail = unsafe { read_volatile(&DEBUG_WRITER.output_tail) };
         let len = unsafe { DEBUG_WRITER.output_buffer.len() };
+        let mut bytes = s.as_bytes();
 
-        let remaining_bytes = if head >= tail {
-            let bytes = s.as_bytes();
-
+        if head >= tail {
             // First write from current head to end of buffer in memory
             let mut backside_len = len - head;
             if tail == 0 {
@@ -187,14 +192,8 @@ impl Write for DebugWriter {
                 backside_len -= 1;
             }
 
-            let written = if backside_len != 0 {
-                let start = head;
-                let end = head + backside_len;
-                DebugWriter::write_buffer(start, end, bytes);
-                min(end - start, bytes.len())
-            } else {
-                0
-            };
+            let written = min(backside_len, bytes.len());
+            DebugWriter::write_buffer(head, head + written, bytes);
 
             // Advance and possibly wrap the head
             head += written;
@@ -202,42 +201,18 @@ impl Write for DebugWriter {
                 head = 0;
             }
 
-            let remaining_bytes = &bytes[written..];
-
-            remaining_bytes
-        } else {
-            s.as_bytes()
-        };
+            bytes = &bytes[written..];
+        }
 
         // At this point, either
         //  o head < tail
         //  o head = len-1, tail = 0 (buffer full edge case)
         //  o there are no more bytes to write
 
-        if remaining_bytes.len() != 0 {
-            // Now write from the head up to tail
-            let start = head;
-            let end = tail;
-            if (tail == 0) && (head == len - 1) {
-                let active = unsafe { read_volatile(&DEBUG_WRITER.output_active_len) };
-                panic!("Debug buffer full. Head {} tail {} len {} active {} remaining {}",
-                       head,
-                       tail,
-                       len,
-                       active,
-                       remaining_bytes.len());
-            }
-            if remaining_bytes.len() > end - start {
-                let active = unsafe { read_volatile(&DEBUG_WRITER.output_active_len) };
-                panic!("Debug buffer out of room. Head {} tail {} len {} active {} remaining {}",
-                       head,
-                       tail,
-                       len,
-                       active,
-                       remaining_bytes.len());
-            }
-            DebugWriter::write_buffer(start, end, remaining_bytes);
-            let written = min(end - start, remaining_bytes.len());
+        if bytes.len() != 0 && head < tail {
+            // Now write from the head up to, but not onto, the tail
+            let written = min(tail - head - 1, bytes.len());
+            DebugWriter::write_buffer(head, head + written, bytes);
 
             // head cannot wrap here
             head += written;
@@ -278,6 +253,108 @@ pub fn begin_debug(msg: &str, file_line: &(&'static str, u32)) {
     }
 }
 
+/// Writes a multi-line report through the kernel debug interface.
+///
+/// `f` is handed the debug writer after the `TOCK_DEBUG` prefix and the
+/// buffer is published once it returns. Whatever does not fit in what is left
+/// of the 1024 byte debug buffer is dropped, so a report never panics, and if
+/// no console is configured yet the report stays in the buffer for `flush`.
+pub fn begin_debug_report<F>(f: F)
+    where F: FnOnce(&mut DebugWriter)
+{
+    unsafe {
+        let count = read_volatile(&DEBUG_WRITER.count);
+        write_volatile(&mut DEBUG_WRITER.count, count + 1);
+
+        let writer = &mut DEBUG_WRITER;
+        let _ = writer.write_fmt(format_args!("TOCK_DEBUG({}):\n", count));
+        f(writer);
+        if writer.driver.is_some() {
+            writer.publish_str();
+        }
+    }
+}
+
+/// Writes whatever is still in the debug buffer to `writer` and empties the
+/// buffer.
+///
+/// This is for when the console can no longer drain the buffer on its own,
+/// such as after a panic, so `writer` must write synchronously. Bytes that
+/// were already handed to the console are written again, since there is no
+/// telling whether they made it out.
+pub unsafe fn flush<W: Write>(writer: &mut W) {
+    let head = read_volatile(&DEBUG_WRITER.output_head);
+    let tail = read_volatile(&DEBUG_WRITER.output_tail);
+    if head == tail {
+        return;
+    }
+
+    let _ = writer.write_str("\r\n---| Debug buffer |---\r\n");
+    if tail > head {
+        write_bytes(writer, &DEBUG_WRITER.output_buffer[tail..]);
+        write_bytes(writer, &DEBUG_WRITER.output_buffer[..head]);
+    } else {
+        write_bytes(writer, &DEBUG_WRITER.output_buffer[tail..head]);
+    }
+
+    write_volatile(&mut DEBUG_WRITER.output_active_len, 0);
+    write_volatile(&mut DEBUG_WRITER.output_head, 0);
+    write_volatile(&mut DEBUG_WRITER.output_tail, 0);
+}
+
+/// Writes raw bytes from the debug buffer. A message may be split where the
+/// buffer wraps around, possibly inside a character, so bytes that are not
+/// valid UTF-8 are replaced by `?`.
+fn write_bytes<W: Write>(writer: &mut W, mut bytes: &[u8]) {
+    while bytes.len() > 0 {
+        match str::from_utf8(bytes) {
+            Ok(s) => {
+                let _ = writer.write_str(s);
+                return;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                let _ = writer.write_str(unsafe { str::from_utf8_unchecked(&bytes[..valid]) });
+                let _ = writer.write_str("?");
+                bytes = &bytes[valid + 1..];
+            }
+        }
+    }
+}
+
+/// Reports a kernel panic on `writer`, which must write synchronously.
+///
+/// Boards call this from their `panic_fmt` before signalling the panic, for
+/// example by blinking an LED. The report holds the panic message, the
+/// contents of the debug buffer, the fault status registers, the fault report
+/// of every process that faulted and, for every process, its state, stacked
+/// registers, memory layout, queued tasks and syscall count.
+pub unsafe fn panic<W: Write>(writer: &mut W, args: Arguments, file: &'static str, line: u32) {
+    let _ = writer.write_fmt(format_args!("\r\n\nKernel panic at {}:{}:\r\n\t\"", file, line));
+    let _ = write(writer, args);
+    let _ = writer.write_str("\"\r\n");
+
+    flush(writer);
+
+    let _ = writer.write_fmt(format_args!("\r\n---| Fault Status |---\r\n"));
+    process::FaultInfo::capture().write_status(writer);
+
+    let procs = &mut process::PROCS;
+    for slot in procs.iter_mut() {
+        slot.as_mut().map(|process| if process.current_state() == process::State::Fault {
+            process.fault_str(writer);
+        });
+    }
+
+    let _ = writer.write_fmt(format_args!("\r\n---| App Status |---\r\n"));
+    for slot in procs.iter_mut() {
+        slot.as_mut().map(|process| {
+            process.statistics_str(writer);
+            process.tasks_str(writer);
+        });
+    }
+}
+
 #[macro_export]
 macro_rules! debug {
     () => ({
@@ -311,3 +388,40 @@ impl Default for Debug {
                buf);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write;
+    use super::{begin_debug_report, flush};
+
+    /// Writes a report about as long as a process fault report.
+    fn report(writer: &mut Write) {
+        for line in 0..20 {
+            let _ = writer.write_fmt(format_args!("line {:02}: {}\n", line, "x".repeat(25)));
+        }
+    }
+
+    #[test]
+    fn reports_past_buffer_end_are_truncated() {
+        // No console is configured, so nothing drains the buffer
+        begin_debug_report(|writer| report(writer));
+        begin_debug_report(|writer| report(writer));
+
+        let mut out = String::new();
+        unsafe {
+            flush(&mut out);
+        }
+        let mut first = String::new();
+        report(&mut first);
+        assert!(out.contains(&first));
+        assert!(out.len() < 2 * first.len());
+
+        // The buffer is usable again once it has been flushed
+        begin_debug_report(|writer| report(writer));
+        let mut out = String::new();
+        unsafe {
+            flush(&mut out);
+        }
+        assert!(out.ends_with(&first));
+    }
+}
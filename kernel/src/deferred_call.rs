@@ -0,0 +1,176 @@
+//! Deferred procedure calls, for capsules to finish work outside of the
+//! callback that started it.
+//!
+//! A capsule registers itself once during board setup with `register`, which
+//! returns the `DeferredCall` handle the capsule keeps. Calling `set` on the
+//! handle, typically from a callback such as `fired` or `read_write_done`,
+//! makes `kernel::main` call the capsule's `handle_deferred_call` once the
+//! chip's pending interrupts have been serviced and before any process runs.
+//!
+//! A call set several times before it runs runs once. A call set while
+//! deferred calls are being handled runs on the next pass through the main
+//! loop, after interrupts have been serviced again and a process has had the
+//! chance to run, so a long state machine can do one step per deferred call
+//! without holding off interrupts or processes.
+
+use common::VolatileCell;
+use support;
+
+pub trait DeferredCallClient {
+    fn handle_deferred_call(&self);
+}
+
+/// Number of deferred calls that can be registered.
+const MAX_DEFERRED_CALLS: usize = 16;
+
+static mut CLIENTS: [Option<&'static DeferredCallClient>; MAX_DEFERRED_CALLS] =
+    [None; MAX_DEFERRED_CALLS];
+
+/// Bit `i` is set while the call registered in `CLIENTS[i]` is pending.
+static mut PENDING: VolatileCell<usize> = VolatileCell::new(0);
+
+/// Handle to a registered deferred call.
+#[derive(Clone, Copy)]
+pub struct DeferredCall {
+    index: usize,
+}
+
+impl DeferredCall {
+    /// Asks for the client's `handle_deferred_call` to be called from the
+    /// main loop.
+    pub fn set(&self) {
+        unsafe {
+            support::atomic(|| PENDING.set(PENDING.get() | (1 << self.index)));
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        unsafe { PENDING.get() & (1 << self.index) != 0 }
+    }
+}
+
+/// Registers `client` and returns the handle it uses to post deferred calls,
+/// or `None` if too many calls are registered.
+pub unsafe fn register(client: &'static DeferredCallClient) -> Option<DeferredCall> {
+    CLIENTS.iter().position(|c| c.is_none()).map(|index| {
+        CLIENTS[index] = Some(client);
+        DeferredCall { index: index }
+    })
+}
+
+/// Whether any deferred call is pending.
+pub fn has_tasks() -> bool {
+    unsafe { PENDING.get() != 0 }
+}
+
+/// Runs each deferred call that is pending when `service` is called.
+pub unsafe fn service() {
+    let pending = support::atomic(|| {
+        let pending = PENDING.get();
+        PENDING.set(0);
+        pending
+    });
+
+    for (index, client) in CLIENTS.iter().enumerate() {
+        if pending & (1 << index) != 0 {
+            client.map(|client| client.handle_deferred_call());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use std::sync::atomic::{ATOMIC_BOOL_INIT, AtomicBool, Ordering};
+    use super::{DeferredCall, DeferredCallClient, has_tasks, register, service};
+
+    /// Counts its calls and, if given a handle, sets itself again from the
+    /// first one.
+    struct Counter {
+        calls: Cell<usize>,
+        repost: Cell<Option<DeferredCall>>,
+    }
+
+    impl DeferredCallClient for Counter {
+        fn handle_deferred_call(&self) {
+            self.calls.set(self.calls.get() + 1);
+            self.repost.get().map(|call| call.set());
+            self.repost.set(None);
+        }
+    }
+
+    fn counter() -> &'static Counter {
+        let counter = Box::new(Counter {
+            calls: Cell::new(0),
+            repost: Cell::new(None),
+        });
+        unsafe { &*Box::into_raw(counter) }
+    }
+
+    /// Deferred calls are global, so tests hold this while using them.
+    struct Exclusive;
+
+    static BUSY: AtomicBool = ATOMIC_BOOL_INIT;
+
+    fn exclusive() -> Exclusive {
+        while BUSY.swap(true, Ordering::Acquire) {}
+        Exclusive
+    }
+
+    impl Drop for Exclusive {
+        fn drop(&mut self) {
+            BUSY.store(false, Ordering::Release);
+        }
+    }
+
+    #[test]
+    fn set_call_runs_once() {
+        let _exclusive = exclusive();
+        let client = counter();
+        let call = unsafe { register(client) }.unwrap();
+        assert!(!call.is_pending());
+
+        call.set();
+        call.set();
+        assert!(call.is_pending());
+        assert!(has_tasks());
+
+        unsafe { service() };
+        assert_eq!(client.calls.get(), 1);
+        assert!(!call.is_pending());
+        assert!(!has_tasks());
+
+        unsafe { service() };
+        assert_eq!(client.calls.get(), 1);
+    }
+
+    #[test]
+    fn only_set_calls_run() {
+        let _exclusive = exclusive();
+        let (set, unset) = (counter(), counter());
+        let call = unsafe { register(set) }.unwrap();
+        unsafe { register(unset) }.unwrap();
+
+        call.set();
+        unsafe { service() };
+        assert_eq!(set.calls.get(), 1);
+        assert_eq!(unset.calls.get(), 0);
+    }
+
+    #[test]
+    fn call_set_during_service_runs_next_time() {
+        let _exclusive = exclusive();
+        let client = counter();
+        let call = unsafe { register(client) }.unwrap();
+        client.repost.set(Some(call));
+
+        call.set();
+        unsafe { service() };
+        assert_eq!(client.calls.get(), 1);
+        assert!(call.is_pending());
+
+        unsafe { service() };
+        assert_eq!(client.calls.get(), 2);
+        assert!(!call.is_pending());
+    }
+}
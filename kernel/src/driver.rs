@@ -33,6 +33,21 @@
 //! command can also return more information, like the number of supported
 //! devices (useful for things like the number of LEDs).
 //!
+//! ## Revoking callbacks and buffers
+//!
+//! An application withdraws a callback by subscribing with a null function
+//! pointer, and a buffer by allowing a null or zero-length buffer. The driver
+//! is then passed `None` instead of the callback or buffer, and must drop
+//! whatever it held for that minor number and application, so that it no
+//! longer calls into or accesses that part of the application. Revoking
+//! something that was never passed to the driver is not an error.
+//!
+//! Drivers that keep a single callback or buffer for all applications, rather
+//! than one per application in a `Container`, only drop it when it belongs to
+//! the revoking application, so that one application cannot take away what
+//! another passed in. `Callback::app_id` and `AppSlice::app_id` tell who that
+//! is.
+//!
 //! # The `yield` System-call
 //!
 //! While drivers do not handle the `yield` system call, it is important to
@@ -64,13 +79,20 @@ pub trait Driver {
     /// application, and the application is responsible for virtualizing that
     /// timer if it needs to.
     ///
+    /// A `callback` of `None` means the application `app_id` is revoking
+    /// the callback it subscribed with `minor_num`.
+    ///
     /// The driver should signal success or failure through the sign of the
     /// return value from `subscribe`. A negative return value signifies an
     /// error, while positive a return values signifies success. In addition,
     /// the magnitude of the return value of can signify extra information such
     /// as error type.
     #[allow(unused_variables)]
-    fn subscribe(&self, minor_num: usize, callback: ::Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 minor_num: usize,
+                 callback: Option<::Callback>,
+                 app_id: ::AppId)
+                 -> ReturnCode {
         ReturnCode::ENOSUPPORT
     }
 
@@ -99,8 +121,15 @@ pub trait Driver {
     /// The buffer is __shared__ between the application and driver, meaning the
     /// driver should not rely on the contents of the buffer to remain
     /// unchanged.
+    ///
+    /// A `slice` of `None` means the application is revoking the buffer it
+    /// allowed with `minor_num`.
     #[allow(unused_variables)]
-    fn allow(&self, app: ::AppId, minor_num: usize, slice: ::AppSlice<::Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             app: ::AppId,
+             minor_num: usize,
+             slice: Option<::AppSlice<::Shared, u8>>)
+             -> ReturnCode {
         ReturnCode::ENOSUPPORT
     }
 }
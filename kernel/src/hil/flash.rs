@@ -1,8 +1,4 @@
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub enum Error {
-    PageBoundary,
-    WordBoundary,
-}
+use returncode::ReturnCode;
 
 /// A block of writable persistent flash memory.
 pub trait Flash {
@@ -13,7 +9,8 @@ pub trait Flash {
     /// Read data
     fn read(&self, offset: usize, buf: &'static mut [u8]);
 
-    /// Write data
+    /// Write data. Flash that can only be written a page at a time requires
+    /// `offset` to be page aligned and `buf` to be one page long.
     fn write(&self, offset: usize, buf: &'static mut [u8]);
 
     /// Erase flash
@@ -21,13 +18,17 @@ pub trait Flash {
 }
 
 /// Implement Client to receive callbacks from Flash
+///
+/// Each operation completes with `SUCCESS`, with `EINVAL` if the offset or
+/// length is misaligned or out of range, or with `FAIL` if the flash
+/// controller reported an error.
 pub trait Client {
     /// Flash read complete
-    fn read_complete(&self, read_buffer: &'static mut [u8], error: Error);
+    fn read_complete(&self, read_buffer: &'static mut [u8], result: ReturnCode);
 
     /// Flash write complete
-    fn write_complete(&self, write_buffer: &'static mut [u8], error: Error);
+    fn write_complete(&self, write_buffer: &'static mut [u8], result: ReturnCode);
 
     /// Flash erase complete
-    fn erase_complete(&self, error: Error);
+    fn erase_complete(&self, result: ReturnCode);
 }
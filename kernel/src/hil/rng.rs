@@ -20,7 +20,7 @@
 //! The following example is a simple capsule that prints out a random number
 //! once a second using the `Alarm` and `RNG` traits.
 //!
-//! ```
+//! ```ignore
 //! struct RngTest<'a, A: Alarm + 'a> {
 //!     rng: &'a RNG,
 //!     alarm: &'a A
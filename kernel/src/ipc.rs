@@ -70,11 +70,15 @@ impl IPC {
 }
 
 impl Driver for IPC {
-    fn subscribe(&self, subscribe_num: usize, callback: Callback) -> ReturnCode {
+    fn subscribe(&self,
+                 subscribe_num: usize,
+                 callback: Option<Callback>,
+                 app_id: AppId)
+                 -> ReturnCode {
         match subscribe_num {
             0 /* Service callback */ => {
-                self.data.enter(callback.app_id(), |data, _| {
-                    data.callback = Some(callback);
+                self.data.enter(app_id, |data, _| {
+                    data.callback = callback;
                     ReturnCode::SUCCESS
                 }).unwrap_or(ReturnCode::EBUSY)
             }
@@ -82,8 +86,8 @@ impl Driver for IPC {
                 if svc_id - 1 >= 8 {
                     ReturnCode::EINVAL /* Maximum of 8 IPC's exceeded */
                 } else {
-                    self.data.enter(callback.app_id(), |data, _| {
-                        data.client_callbacks[svc_id - 1] = Some(callback);
+                    self.data.enter(app_id, |data, _| {
+                        data.client_callbacks[svc_id - 1] = callback;
                         ReturnCode::SUCCESS
                     }).unwrap_or(ReturnCode::EBUSY)
                 }
@@ -112,9 +116,13 @@ impl Driver for IPC {
             .unwrap_or(ReturnCode::EINVAL) /* Request to IPC to unknown process */
     }
 
-    fn allow(&self, appid: AppId, target_id: usize, slice: AppSlice<Shared, u8>) -> ReturnCode {
+    fn allow(&self,
+             appid: AppId,
+             target_id: usize,
+             slice: Option<AppSlice<Shared, u8>>)
+             -> ReturnCode {
         if target_id == 0 {
-            if slice.len() > 0 {
+            if let Some(slice) = slice {
                 let procs = unsafe { &mut process::PROCS };
                 for (i, process) in procs.iter().enumerate() {
                     match process {
@@ -139,7 +147,14 @@ impl Driver for IPC {
                 data.shared_memory
                     .get_mut(target_id - 1)
                     .map(|smem| {
-                        *smem = Some(slice);
+                        // The target can no longer reach memory that is
+                        // revoked or replaced
+                        if let Some(ref old) = *smem {
+                            unsafe {
+                                old.unexpose_from(AppId::new(target_id - 1));
+                            }
+                        }
+                        *smem = slice;
                         ReturnCode::SUCCESS
                     })
                     .unwrap_or(ReturnCode::EINVAL) /* Target process does not exist */
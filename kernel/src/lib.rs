@@ -1,17 +1,26 @@
 #![feature(asm,core_intrinsics,unique,nonzero,const_fn,lang_items)]
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+
+// Unit tests are built for the host against the standard library, which does
+// not bring `core` into scope on its own.
+#[cfg(test)]
+extern crate core;
 
 pub mod common;
 
 pub mod callback;
 pub mod container;
+pub mod deferred_call;
 #[macro_use]
 pub mod debug;
 pub mod driver;
 pub mod ipc;
 pub mod mem;
 pub mod process;
+pub mod restart;
 pub mod returncode;
+pub mod scheduler;
+pub mod tbf;
 pub mod hil;
 
 pub mod support;
@@ -29,11 +38,17 @@ pub use platform::{Chip, mpu, Platform, systick};
 pub use platform::systick::SysTick;
 pub use process::{Process, State};
 pub use returncode::ReturnCode;
+pub use scheduler::Scheduler;
 
-pub fn main<P: Platform, C: Chip>(platform: &P,
-                                  chip: &mut C,
-                                  processes: &'static mut [Option<process::Process<'static>>],
-                                  ipc: &ipc::IPC) {
+pub fn main<P, C, S>(platform: &P,
+                     chip: &mut C,
+                     processes: &'static mut [Option<process::Process<'static>>],
+                     ipc: &ipc::IPC,
+                     scheduler: &S)
+    where P: Platform,
+          C: Chip,
+          S: Scheduler
+{
     let processes = unsafe {
         process::PROCS = processes;
         &mut process::PROCS
@@ -42,18 +57,28 @@ pub fn main<P: Platform, C: Chip>(platform: &P,
     loop {
         unsafe {
             chip.service_pending_interrupts();
+            deferred_call::service();
 
-            for (i, p) in processes.iter_mut().enumerate() {
-                p.as_mut().map(|process| {
-                    sched::do_process(platform, chip, process, AppId::new(i), ipc);
+            // A process gets to run between rounds of deferred calls, so a
+            // call that keeps setting itself cannot starve processes
+            let mut ran_process = false;
+            while !chip.has_pending_interrupts() &&
+                  !(ran_process && deferred_call::has_tasks()) {
+                let next = scheduler.next(processes);
+                let i = match next {
+                    Some(i) => i,
+                    None => break,
+                };
+                let timeslice = scheduler.timeslice(i);
+                processes[i].as_mut().map(|process| {
+                    sched::do_process(platform, chip, process, AppId::new(i), ipc, timeslice);
                 });
-                if chip.has_pending_interrupts() {
-                    break;
-                }
+                ran_process = true;
             }
 
-            support::atomic(|| if !chip.has_pending_interrupts() && process::processes_blocked() {
-                support::wfi();
+            support::atomic(|| if !chip.has_pending_interrupts() && !deferred_call::has_tasks() &&
+                                  process::processes_blocked() {
+                chip.sleep();
             })
         };
     }
@@ -68,6 +68,11 @@ impl<L, T> AppSlice<L, T> {
         self.len
     }
 
+    /// The application whose memory the slice points into.
+    pub fn app_id(&self) -> AppId {
+        self.ptr.process
+    }
+
     pub unsafe fn ptr(&self) -> *const T {
         self.ptr.ptr.get() as *const T
     }
@@ -84,6 +89,19 @@ impl<L, T> AppSlice<L, T> {
         }
     }
 
+    /// Takes away the access to the slice given to `appid` by `expose_to`.
+    pub unsafe fn unexpose_from(&self, appid: AppId) -> bool {
+        let ps = &mut process::PROCS;
+        if appid.idx() != self.ptr.process.idx() && ps.len() > appid.idx() {
+            ps[appid.idx()]
+                .as_ref()
+                .map(|process| process.remove_mpu_region(self.ptr() as *const u8))
+                .unwrap_or(false)
+        } else {
+            false
+        }
+    }
+
     pub fn iter(&self) -> slice::Iter<T> {
         self.as_ref().iter()
     }
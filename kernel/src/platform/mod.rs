@@ -1,4 +1,5 @@
 use driver::Driver;
+use support;
 
 pub mod mpu;
 pub mod systick;
@@ -15,4 +16,12 @@ pub trait Chip {
     fn has_pending_interrupts(&self) -> bool;
     fn mpu(&self) -> &Self::MPU;
     fn systick(&self) -> &Self::SysTick;
+
+    /// Puts the chip to sleep until the next interrupt. Called with
+    /// interrupts disabled when no process is ready to run.
+    fn sleep(&self) {
+        unsafe {
+            support::wfi();
+        }
+    }
 }
@@ -32,6 +32,8 @@ pub trait MPU {
     /// `start_addr`: the region base address. Lower bits will be masked
     ///               according to the region size.
     /// `len`       : region size as a function 2^(len + 1)
+    /// `subregions`: bit `i` disables the `i`th eighth of the region. Only
+    ///               regions of 256 bytes or more have subregions.
     /// `execute`   : whether to enable code execution from this region
     /// `ap`        : access permissions as defined in Table 4.47 of the user
     ///               guide.
@@ -39,6 +41,7 @@ pub trait MPU {
                region_num: u32,
                start_addr: u32,
                len: u32,
+               subregions: u8,
                execute: ExecutePermission,
                ap: AccessPermission);
 }
@@ -47,5 +50,12 @@ pub trait MPU {
 impl MPU for () {
     fn enable_mpu(&self) {}
 
-    fn set_mpu(&self, _: u32, _: u32, _: u32, _: ExecutePermission, _: AccessPermission) {}
+    fn set_mpu(&self,
+               _: u32,
+               _: u32,
+               _: u32,
+               _: u8,
+               _: ExecutePermission,
+               _: AccessPermission) {
+    }
 }
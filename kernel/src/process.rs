@@ -1,16 +1,20 @@
 use callback::AppId;
 use common::{RingBuffer, Queue, VolatileCell};
+use common::p256;
 
 use container;
-use core::{mem, ptr, slice, str};
+use debug;
+use core::{cmp, mem, ptr, slice, str};
 use core::cell::Cell;
 use core::fmt::Write;
 use core::intrinsics;
 use core::ptr::{read_volatile, write_volatile};
 
 use platform::mpu;
+use restart;
 use returncode::ReturnCode;
 use syscall::Syscall;
+use tbf::{self, LoadInfo, parse_and_validate_load_info};
 
 /// Takes a value and rounds it up to be aligned % 8
 macro_rules! align8 {
@@ -26,6 +30,7 @@ pub static mut APP_FAULT: usize = 0;
 #[no_mangle]
 pub static mut SCB_REGISTERS: [u32; 5] = [0; 5];
 
+#[cfg(target_os = "none")]
 #[allow(improper_ctypes)]
 extern "C" {
     pub fn switch_to_user(user_stack: *const u8,
@@ -34,8 +39,28 @@ extern "C" {
                           -> *mut u8;
 }
 
+/// Processes cannot run when the kernel is built for the host, for unit tests
+/// or the host simulation board.
+#[cfg(not(target_os = "none"))]
+pub unsafe fn switch_to_user(_user_stack: *const u8,
+                             _mem_base: *const u8,
+                             _process_regs: &mut [usize; 8])
+                             -> *mut u8 {
+    panic!("processes cannot run on the host");
+}
+
 pub static mut PROCS: &'static mut [Option<Process<'static>>] = &mut [];
 
+/// Public key that app images must be signed with, if any.
+static mut APP_SIGNING_KEY: Option<&'static [u8; p256::PUBLIC_KEY_LEN]> = None;
+
+/// Requires every app loaded from now on to carry a valid ECDSA P-256
+/// signature made with the private key matching `key`. The key is the
+/// big-endian X and Y coordinates of the public point.
+pub unsafe fn set_app_signing_key(key: &'static [u8; p256::PUBLIC_KEY_LEN]) {
+    APP_SIGNING_KEY = Some(key);
+}
+
 pub fn schedule(callback: FunctionCall, appid: AppId) -> bool {
     let procs = unsafe { &mut PROCS };
     let idx = appid.idx();
@@ -46,16 +71,99 @@ pub fn schedule(callback: FunctionCall, appid: AppId) -> bool {
     match procs[idx] {
         None => false,
         Some(ref mut p) => {
+            // A faulted process never runs its callbacks, and queueing them
+            // would keep the kernel from sleeping
+            if p.state == State::Fault {
+                return false;
+            }
             // TODO(alevy): validate appid liveness
-            unsafe {
-                HAVE_WORK.set(HAVE_WORK.get() + 1);
+            let enqueued = p.tasks.enqueue(Task::FunctionCall(callback));
+            if !enqueued {
+                if p.callbacks_dropped.get() == 0 {
+                    debug!("{}: callback queue full, dropping callbacks", p.package_name);
+                }
+                p.callbacks_dropped.set(p.callbacks_dropped.get() + 1);
+            } else if p.state != State::Stopped {
+                // A stopped process keeps its callbacks queued until it is
+                // resumed, when they are counted as work
+                unsafe {
+                    HAVE_WORK.set(HAVE_WORK.get() + 1);
+                }
             }
-
-            p.tasks.enqueue(Task::FunctionCall(callback))
+            enqueued
         }
     }
 }
 
+/// App memory left over after the apps in flash were loaded at boot, and how
+/// apps loaded into it later respond to faults.
+static mut DYNAMIC_APP_MEMORY: Option<(*mut u8, usize, FaultResponse)> = None;
+
+/// Makes `len` bytes of app memory starting at `start` available to apps
+/// installed after boot with `load_dynamic`.
+pub unsafe fn set_dynamic_app_memory(start: *mut u8, len: usize, fault_response: FaultResponse) {
+    DYNAMIC_APP_MEMORY = Some((start, len, fault_response));
+}
+
+/// Loads the app whose image starts at `app_flash_address` into a free
+/// process slot, using the memory set aside with `set_dynamic_app_memory`.
+/// The process starts running as soon as the scheduler picks it.
+///
+/// Returns the index of the process slot.
+pub unsafe fn load_dynamic(app_flash_address: *const u8) -> Result<usize, LoadError> {
+    let idx = match PROCS.iter().position(|p| p.is_none()) {
+        Some(idx) => idx,
+        None => return Err(LoadError::NoFreeSlot),
+    };
+    let (memory, memory_len, fault_response) = match DYNAMIC_APP_MEMORY {
+        Some(dynamic) => dynamic,
+        None => return Err(LoadError::NoDynamicMemory),
+    };
+
+    let (process, _, memory_offset) =
+        Process::create(app_flash_address, memory, memory_len, fault_response);
+    match process {
+        Ok(process) => PROCS[idx] = Some(process),
+        Err(err) => return Err(err),
+    }
+    DYNAMIC_APP_MEMORY = Some((memory.offset(memory_offset as isize),
+                               memory_len - memory_offset,
+                               fault_response));
+    Ok(idx)
+}
+
+/// Returns the number of process slots, loaded or not.
+pub fn num_processes() -> usize {
+    unsafe { PROCS.len() }
+}
+
+/// Calls `f` with the process at index `idx`, if there is one.
+pub fn with_process<F, R>(idx: usize, f: F) -> Option<R>
+    where F: FnOnce(&Process<'static>) -> R
+{
+    unsafe { PROCS.get(idx).and_then(|p| p.as_ref()).map(f) }
+}
+
+/// Calls `f` with a mutable reference to the process at index `idx`, if there
+/// is one. `f` must not be used on the process that is currently running.
+pub unsafe fn with_process_mut<F, R>(idx: usize, f: F) -> Option<R>
+    where F: FnOnce(&mut Process<'static>) -> R
+{
+    PROCS.get_mut(idx).and_then(|p| p.as_mut()).map(f)
+}
+
+/// Whether `appid` is the app named `package_name` in a signed image. A
+/// package name is chosen by whoever builds the image, so it only identifies
+/// an app when the board requires images to be signed; without a signing key
+/// this is always false. Drivers that give one app privileges over the others
+/// should identify it with this.
+pub fn is_trusted_app(appid: AppId, package_name: &str) -> bool {
+    if unsafe { APP_SIGNING_KEY.is_none() } {
+        return false;
+    }
+    with_process(appid.idx(), |p| p.package_name == package_name).unwrap_or(false)
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Error {
     NoSuchApp,
@@ -63,17 +171,271 @@ pub enum Error {
     AddressOutOfBounds,
 }
 
+/// The code drivers return when they cannot enter an app's container.
+impl From<Error> for ReturnCode {
+    fn from(err: Error) -> ReturnCode {
+        match err {
+            Error::OutOfMemory => ReturnCode::ENOMEM,
+            Error::AddressOutOfBounds => ReturnCode::EINVAL,
+            Error::NoSuchApp => ReturnCode::EINVAL,
+        }
+    }
+}
+
+/// Reasons an app image could not be loaded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LoadError {
+    /// There is no valid header at the image address. Usually this means
+    /// there are no more apps.
+    NoValidHeader,
+    /// The image does not match the SHA-256 hash in its header.
+    HashMismatch,
+    /// The board requires signed apps and the image's signature is missing
+    /// or does not verify.
+    BadSignature,
+    /// The app needs more memory than is left.
+    InsufficientMemory { requested: usize, available: usize },
+    /// The entry point is not a Thumb address.
+    EntryNotThumb(usize),
+    /// Every process slot is in use.
+    NoFreeSlot,
+    /// The board has not set aside memory with `set_dynamic_app_memory` for
+    /// apps loaded after boot.
+    NoDynamicMemory,
+    /// The header asks for a callback queue longer than
+    /// `MAX_CALLBACK_QUEUE_LEN`.
+    CallbackQueueTooLong(usize),
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum State {
     Running,
     Yielded,
     Fault,
+    /// Stopped on request until it is resumed or restarted. Tasks are still
+    /// queued for a stopped process, but do not count as work.
+    Stopped,
+}
+
+/// The number drivers report a state as to applications.
+impl From<State> for usize {
+    fn from(state: State) -> usize {
+        match state {
+            State::Running => 0,
+            State::Yielded => 1,
+            State::Fault => 2,
+            State::Stopped => 3,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum FaultResponse {
     Panic,
-    Restart,
+    Restart(RestartPolicy),
+}
+
+/// How a faulted process is restarted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RestartPolicy {
+    /// Number of restarts after which the process is stopped for good.
+    pub max_restarts: usize,
+
+    /// Delay before the first restart in milliseconds. The delay doubles
+    /// after every restart. Zero restarts the process immediately.
+    pub backoff_ms: u32,
+}
+
+/// Fault status registers captured when a process faults.
+#[derive(Copy, Clone, Debug)]
+pub struct FaultInfo {
+    pub cfsr: u32,
+    pub hfsr: u32,
+    pub mmfar: u32,
+    pub bfar: u32,
+}
+
+impl FaultInfo {
+    /// Reads the fault status registers as they are now.
+    pub unsafe fn capture() -> FaultInfo {
+        FaultInfo {
+            cfsr: read_volatile(&SCB_REGISTERS[1]),
+            hfsr: read_volatile(&SCB_REGISTERS[2]),
+            mmfar: read_volatile(&SCB_REGISTERS[3]),
+            bfar: read_volatile(&SCB_REGISTERS[4]),
+        }
+    }
+
+    /// Decodes the CFSR and HFSR into one line per fault cause, followed by
+    /// the faulting address if one was captured.
+    pub fn write_status<W: Write>(&self, writer: &mut W) {
+        let cfsr = self.cfsr;
+        let hfsr = self.hfsr;
+        let mmfar = self.mmfar;
+        let bfar = self.bfar;
+
+        let iaccviol = (cfsr & 0x01) == 0x01;
+        let daccviol = (cfsr & 0x02) == 0x02;
+        let munstkerr = (cfsr & 0x08) == 0x08;
+        let mstkerr = (cfsr & 0x10) == 0x10;
+        let mlsperr = (cfsr & 0x20) == 0x20;
+        let mmfarvalid = (cfsr & 0x80) == 0x80;
+
+        let ibuserr = ((cfsr >> 8) & 0x01) == 0x01;
+        let preciserr = ((cfsr >> 8) & 0x02) == 0x02;
+        let impreciserr = ((cfsr >> 8) & 0x04) == 0x04;
+        let unstkerr = ((cfsr >> 8) & 0x08) == 0x08;
+        let stkerr = ((cfsr >> 8) & 0x10) == 0x10;
+        let lsperr = ((cfsr >> 8) & 0x20) == 0x20;
+        let bfarvalid = ((cfsr >> 8) & 0x80) == 0x80;
+
+        let undefinstr = ((cfsr >> 16) & 0x01) == 0x01;
+        let invstate = ((cfsr >> 16) & 0x02) == 0x02;
+        let invpc = ((cfsr >> 16) & 0x04) == 0x04;
+        let nocp = ((cfsr >> 16) & 0x08) == 0x08;
+        let unaligned = ((cfsr >> 16) & 0x100) == 0x100;
+        let divbysero = ((cfsr >> 16) & 0x200) == 0x200;
+
+        let vecttbl = (hfsr & 0x02) == 0x02;
+        let forced = (hfsr & 0x40000000) == 0x40000000;
+
+        if iaccviol {
+            let _ =
+                writer.write_fmt(format_args!("Instruction Access Violation:       {}\r\n",
+                                              iaccviol));
+        }
+        if daccviol {
+            let _ =
+                writer.write_fmt(format_args!("Data Access Violation:              {}\r\n",
+                                              daccviol));
+        }
+        if munstkerr {
+            let _ =
+                writer.write_fmt(format_args!("Memory Management Unstacking Fault: {}\r\n",
+                                              munstkerr));
+        }
+        if mstkerr {
+            let _ = writer.write_fmt(format_args!("Memory Management Stacking Fault:   {}\r\n",
+                                                  mstkerr));
+        }
+        if mlsperr {
+            let _ = writer.write_fmt(format_args!("Memory Management Lazy FP Fault:    {}\r\n",
+                                                  mlsperr));
+        }
+
+        if ibuserr {
+            let _ = writer.write_fmt(format_args!("Instruction Bus Error:              {}\r\n",
+                                                  ibuserr));
+        }
+        if preciserr {
+            let _ =
+                writer.write_fmt(format_args!("Precise Data Bus Error:             {}\r\n",
+                                              preciserr));
+        }
+        if impreciserr {
+            let _ =
+                writer.write_fmt(format_args!("Imprecise Data Bus Error:           {}\r\n",
+                                              impreciserr));
+        }
+        if unstkerr {
+            let _ =
+                writer.write_fmt(format_args!("Bus Unstacking Fault:               {}\r\n",
+                                              unstkerr));
+        }
+        if stkerr {
+            let _ = writer.write_fmt(format_args!("Bus Stacking Fault:                 {}\r\n",
+                                                  stkerr));
+        }
+        if lsperr {
+            let _ = writer.write_fmt(format_args!("Bus Lazy FP Fault:                  {}\r\n",
+                                                  lsperr));
+        }
+
+        if undefinstr {
+            let _ =
+                writer.write_fmt(format_args!("Undefined Instruction Usage Fault:  {}\r\n",
+                                              undefinstr));
+        }
+        if invstate {
+            let _ =
+                writer.write_fmt(format_args!("Invalid State Usage Fault:          {}\r\n",
+                                              invstate));
+        }
+        if invpc {
+            let _ =
+                writer.write_fmt(format_args!("Invalid PC Load Usage Fault:        {}\r\n", invpc));
+        }
+        if nocp {
+            let _ =
+                writer.write_fmt(format_args!("No Coprocessor Usage Fault:         {}\r\n", nocp));
+        }
+        if unaligned {
+            let _ =
+                writer.write_fmt(format_args!("Unaligned Access Usage Fault:       {}\r\n",
+                                              unaligned));
+        }
+        if divbysero {
+            let _ =
+                writer.write_fmt(format_args!("Divide By Zero:                     {}\r\n",
+                                              divbysero));
+        }
+
+        if vecttbl {
+            let _ = writer.write_fmt(format_args!("Bus Fault on Vector Table Read:     {}\r\n",
+                                                  vecttbl));
+        }
+        if forced {
+            let _ = writer.write_fmt(format_args!("Forced Hard Fault:                  {}\r\n",
+                                                  forced));
+        }
+
+        if mmfarvalid {
+            let _ =
+                writer.write_fmt(format_args!("Faulting Memory Address:            {:#010X}\r\n",
+                                              mmfar));
+        }
+        if bfarvalid {
+            let _ =
+                writer.write_fmt(format_args!("Bus Fault Address:                  {:#010X}\r\n",
+                                              bfar));
+        }
+
+        if cfsr == 0 && hfsr == 0 {
+            let _ = writer.write_fmt(format_args!("No faults detected.\r\n"));
+        } else {
+            let _ =
+                writer.write_fmt(format_args!("Fault Status Register (CFSR):       {:#010X}\r\n",
+                                              cfsr));
+            let _ =
+                writer.write_fmt(format_args!("Hard Fault Status Register (HFSR):  {:#010X}\r\n",
+                                              hfsr));
+        }
+    }
+}
+
+/// Accounting information about a process.
+#[derive(Copy, Clone, Debug)]
+pub struct Statistics {
+    pub state: State,
+    pub syscall_count: usize,
+    /// Approximate CPU time in microseconds. Only measured while the process
+    /// runs under a preemptive scheduler.
+    pub cpu_time_us: usize,
+    pub timeslice_expirations: usize,
+    pub callbacks_delivered: usize,
+    /// Callbacks dropped because the task queue was full.
+    pub callbacks_dropped: usize,
+    /// Bytes of grant region in use, including container pointers and the
+    /// task queue.
+    pub grant_bytes_used: usize,
+    /// Bytes of grant memory allocated by containers, which count against
+    /// the grant limit.
+    pub grant_bytes_allocated: usize,
+    pub grant_limit: Option<usize>,
+    /// Grant allocations that failed, for lack of memory or because of the
+    /// grant limit.
+    pub grant_alloc_failures: usize,
+    pub restart_count: usize,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -97,57 +459,25 @@ pub struct FunctionCall {
     pub pc: usize,
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug)]
-struct LoadInfo {
-    version: u32,
-    total_size: u32,
-    entry_offset: u32,
-    rel_data_offset: u32,
-    rel_data_size: u32,
-    text_offset: u32,
-    text_size: u32,
-    got_offset: u32,
-    got_size: u32,
-    data_offset: u32,
-    data_size: u32,
-    bss_mem_offset: u32,
-    bss_size: u32,
-    min_stack_len: u32,
-    min_app_heap_len: u32,
-    min_kernel_heap_len: u32,
-    pkg_name_offset: u32,
-    pkg_name_size: u32,
-    checksum: u32,
-}
-
-/// Converts a pointer to memory to a LoadInfo struct
-///
-/// This function takes a pointer to arbitrary memory and Optionally returns a
-/// LoadInfo struct. This function will validate the header checksum, but does
-/// not perform sanity or security checking on the structure
-unsafe fn parse_and_validate_load_info(address: *const u8) -> Option<&'static LoadInfo> {
-    let load_info = &*(address as *const LoadInfo);
-
-    if load_info.version != 1 {
-        return None;
-    }
+/// Number of callbacks that can be queued for a process whose header does not
+/// specify a callback queue length.
+pub const DEFAULT_CALLBACK_QUEUE_LEN: usize = 10;
 
-    let checksum =
-        load_info.version ^ load_info.total_size ^ load_info.entry_offset ^
-        load_info.rel_data_offset ^ load_info.rel_data_size ^ load_info.text_offset ^
-        load_info.text_size ^ load_info.got_offset ^
-        load_info.got_size ^
-        load_info.data_offset ^ load_info.data_size ^ load_info.bss_mem_offset ^
-        load_info.bss_size ^
-        load_info.min_stack_len ^ load_info.min_app_heap_len ^
-        load_info.min_kernel_heap_len ^ load_info.pkg_name_offset ^ load_info.pkg_name_size;
+/// Most callbacks that an app's header can ask to have queued.
+pub const MAX_CALLBACK_QUEUE_LEN: usize = 256;
 
-    if checksum != load_info.checksum {
-        return None;
-    }
+/// Precedes each allocation in the grant region.
+struct GrantHeader {
+    /// Size of the block in bytes, including this header.
+    size: usize,
+    /// The container the allocation was made for.
+    container_num: u16,
+    free: bool,
+}
 
-    Some(load_info)
+/// Size of `GrantHeader`, rounded up to keep allocations 8-byte aligned.
+fn grant_header_size() -> usize {
+    align8!(mem::size_of::<GrantHeader>())
 }
 
 #[derive(Default)]
@@ -178,6 +508,10 @@ pub struct Process<'a> {
     ///  |   ↓
     ///  |  ----   <- cur_stack
     ///  |
+    ///  |  ----
+    ///  | Guard
+    ///  |  ----   <- stack_guard
+    ///  |
     ///  |  ----   <- app_mem_start
     ///  |  Data
     ///  |======== <- memory[0]
@@ -191,12 +525,43 @@ pub struct Process<'a> {
     cur_stack: *const u8,
     app_mem_start: *const u8,
 
+    /// Start of the inaccessible region just below the stack, so that a
+    /// process overflowing its stack faults rather than overwriting its data.
+    stack_guard: *const u8,
+
+    /// Initial value of `kernel_memory_break`, just below the container
+    /// pointers and the task ring buffer. Used to wipe the grant region when
+    /// the process is restarted.
+    grant_start: *const u8,
+
     /// How many syscalls have occurred since the process started
     syscall_count: Cell<usize>,
 
     /// What was the most recent syscall
     last_syscall: Cell<Option<Syscall>>,
 
+    /// Approximate microseconds of CPU time the process has used
+    cpu_time_us: Cell<usize>,
+
+    /// How many times the process was preempted at the end of its timeslice
+    timeslice_expirations: Cell<usize>,
+
+    /// How many callbacks have been delivered to the process
+    callbacks_delivered: Cell<usize>,
+
+    /// How many callbacks were dropped because the task queue was full
+    callbacks_dropped: Cell<usize>,
+
+    /// Most bytes of grant memory that may be allocated, from the app header
+    grant_limit: Option<usize>,
+
+    /// Bytes of grant memory allocated, including allocation headers
+    grant_allocated: usize,
+
+    /// How many grant allocations failed, for lack of memory or because of
+    /// the limit
+    grant_alloc_failures: Cell<usize>,
+
     /// Process text segment
     text: &'static [u8],
 
@@ -210,7 +575,20 @@ pub struct Process<'a> {
     /// How to deal with Faults occuring in the process
     fault_response: FaultResponse,
 
-    /// MPU regions are saved as a pointer-size pair.
+    /// How many times the process has been restarted after a fault
+    restart_count: usize,
+
+    /// State to return to when a stopped process is resumed
+    stopped_state: State,
+
+    /// Fault status from the most recent fault
+    last_fault: Option<FaultInfo>,
+
+    /// When a faulted process is waiting out its backoff, the time the fault
+    /// occurred and the delay before restarting it, in `RestartTimer` tics.
+    restart_deadline: Option<(u32, u32)>,
+
+    /// MPU regions granted over IPC, saved as a pointer-size pair.
     ///
     /// size is encoded as X where
     /// SIZE = 2^(X + 1) and X >= 4.
@@ -222,22 +600,158 @@ pub struct Process<'a> {
     /// The pointer must be aligned to the size. E.g. if the size is 32 bytes, the pointer must be
     /// 32-byte aligned.
     ///
-    mpu_regions: [Cell<(*const u8, usize)>; 5],
+    mpu_regions: [Cell<(*const u8, usize)>; 4],
 
     tasks: RingBuffer<'a, Task>,
 
     pub package_name: &'static str,
+
+    /// Value of the `Permissions` entry of the app's header. Apps without one
+    /// may use every driver.
+    permissions: Option<&'static [u8]>,
 }
 
-fn closest_power_of_two(mut num: u32) -> u32 {
-    num -= 1;
-    num |= num >> 1;
-    num |= num >> 2;
-    num |= num >> 4;
-    num |= num >> 8;
-    num |= num >> 16;
-    num += 1;
-    num
+/// Smallest MPU region size, in bytes.
+const MPU_MIN_REGION_SIZE: usize = 32;
+
+/// Smallest MPU region size, in bytes, that can be split into subregions.
+const MPU_MIN_SUBREGION_REGION_SIZE: usize = 256;
+
+/// Size in bytes of the guard region below the stack.
+const STACK_GUARD_SIZE: usize = MPU_MIN_REGION_SIZE;
+
+/// Offset from the start of process memory of the stack guard, the first
+/// guard-aligned address after the data segment.
+fn stack_guard_offset(data_len: usize) -> usize {
+    (data_len + STACK_GUARD_SIZE - 1) & !(STACK_GUARD_SIZE - 1)
+}
+
+/// An MPU region, as passed to `mpu::MPU::set_mpu`.
+#[derive(Clone, Copy, Debug)]
+struct MpuRegion {
+    base: usize,
+    /// Region size, encoded as 2^(len + 1) bytes
+    len: u32,
+    /// Disable bits for the region's eight subregions
+    subregions: u8,
+}
+
+impl MpuRegion {
+    /// Returns the lowest address in an enabled subregion.
+    fn start(&self) -> usize {
+        let size = 1usize.wrapping_shl(self.len + 1);
+        if size != 0 && size < MPU_MIN_SUBREGION_REGION_SIZE {
+            self.base
+        } else {
+            self.base + (!self.subregions).trailing_zeros() as usize * (size / 8)
+        }
+    }
+}
+
+/// Returns the smallest MPU region, with some of its subregions disabled,
+/// whose enabled part is exactly `[start, end)`, or `None` if there is none.
+fn mpu_region_exact(start: usize, end: usize) -> Option<MpuRegion> {
+    for exp in MPU_MIN_REGION_SIZE.trailing_zeros()..32 {
+        let size = 1 << exp;
+        let base = start & !(size - 1);
+        if end - base > size {
+            continue;
+        }
+        if size < MPU_MIN_SUBREGION_REGION_SIZE {
+            if base == start && end - start == size {
+                return Some(MpuRegion {
+                    base: base,
+                    len: exp - 1,
+                    subregions: 0,
+                });
+            }
+            continue;
+        }
+        let subregion_size = size / 8;
+        if start % subregion_size != 0 || end % subregion_size != 0 {
+            continue;
+        }
+        let first = (start - base) / subregion_size;
+        let last = (end - base) / subregion_size;
+        let enabled = ((1u32 << last) - 1) & !((1u32 << first) - 1);
+        return Some(MpuRegion {
+            base: base,
+            len: exp - 1,
+            subregions: !enabled as u8,
+        });
+    }
+    None
+}
+
+/// Returns the MPU region, with some of its subregions disabled, that covers
+/// at least `[start, end)` and as little as possible below `start`.
+fn mpu_region_covering(start: usize, end: usize) -> MpuRegion {
+    let mut best: Option<MpuRegion> = None;
+    for exp in MPU_MIN_REGION_SIZE.trailing_zeros()..32 {
+        let size = 1 << exp;
+        let base = start & !(size - 1);
+        if end - base > size {
+            continue;
+        }
+        let region = if size < MPU_MIN_SUBREGION_REGION_SIZE {
+            MpuRegion {
+                base: base,
+                len: exp - 1,
+                subregions: 0,
+            }
+        } else {
+            let first = (start - base) / (size / 8);
+            MpuRegion {
+                base: base,
+                len: exp - 1,
+                subregions: ((1u32 << first) - 1) as u8,
+            }
+        };
+        if best.map_or(true, |best| region.start() > best.start()) {
+            best = Some(region);
+        }
+    }
+    // A 4GB region covers any range
+    best.unwrap_or(MpuRegion {
+        base: 0,
+        len: 31,
+        subregions: 0,
+    })
+}
+
+/// Chooses where in memory starting at `start` to place an app that needs
+/// `min_len` bytes, so that `mpu_region_exact` can cover it while using as
+/// little memory as possible. Returns the start and length of the app's
+/// memory.
+fn app_memory_layout(start: usize, min_len: usize) -> (usize, usize) {
+    let mut best: Option<(usize, usize)> = None;
+    for exp in MPU_MIN_REGION_SIZE.trailing_zeros()..32 {
+        let size = 1 << exp;
+        let granularity = if size < MPU_MIN_SUBREGION_REGION_SIZE {
+            size
+        } else {
+            size / 8
+        };
+        let app_len = (min_len + granularity - 1) & !(granularity - 1);
+        if app_len > size {
+            continue;
+        }
+
+        // Move to the next region-aligned window if the app would straddle
+        // two of them
+        let mut app_start = (start + granularity - 1) & !(granularity - 1);
+        if size < MPU_MIN_SUBREGION_REGION_SIZE ||
+           (app_start & (size - 1)) + app_len > size {
+            app_start = (start + size - 1) & !(size - 1);
+        }
+
+        if best.map_or(true, |(best_start, best_len)| {
+            app_start + app_len < best_start + best_len
+        }) {
+            best = Some((app_start, app_len));
+        }
+    }
+    best.unwrap_or((start, min_len))
 }
 
 // Stores the current number of callbacks enqueued + processes in Running state
@@ -249,16 +763,104 @@ pub fn processes_blocked() -> bool {
 
 impl<'a> Process<'a> {
     pub fn schedule_ipc(&mut self, from: AppId, cb_type: IPCType) {
-        unsafe {
-            HAVE_WORK.set(HAVE_WORK.get() + 1);
+        if self.state == State::Fault {
+            return;
+        }
+        if !self.tasks.enqueue(Task::IPC((from, cb_type))) {
+            self.callbacks_dropped.set(self.callbacks_dropped.get() + 1);
+        } else if self.state != State::Stopped {
+            unsafe {
+                HAVE_WORK.set(HAVE_WORK.get() + 1);
+            }
         }
-        self.tasks.enqueue(Task::IPC((from, cb_type)));
     }
 
     pub fn current_state(&self) -> State {
         self.state
     }
 
+    /// Whether the process has work to do: it is running or has tasks queued.
+    pub fn ready(&self) -> bool {
+        match self.state {
+            State::Running => true,
+            State::Yielded => self.tasks.has_elements(),
+            State::Fault => false,
+            State::Stopped => false,
+        }
+    }
+
+    /// Number of units of work the process contributes to `HAVE_WORK`: its
+    /// queued tasks, plus one if it is running. Faulted and stopped processes
+    /// do not run, so they contribute nothing.
+    fn pending_work(&self) -> usize {
+        match self.state {
+            State::Running => self.tasks.len() + 1,
+            State::Yielded => self.tasks.len(),
+            State::Fault | State::Stopped => 0,
+        }
+    }
+
+    /// Stops a running or yielded process. It is not scheduled again until
+    /// `resume` or `restart` is called. Returns false if the process is
+    /// faulted or already stopped.
+    pub fn stop(&mut self) -> bool {
+        match self.state {
+            State::Running | State::Yielded => {
+                unsafe {
+                    HAVE_WORK.set(HAVE_WORK.get() - self.pending_work());
+                }
+                self.stopped_state = self.state;
+                self.state = State::Stopped;
+                true
+            }
+            State::Fault | State::Stopped => false,
+        }
+    }
+
+    /// Lets a stopped process run again from where it was stopped, and
+    /// delivers the tasks queued for it in the meantime. Returns false if the
+    /// process was not stopped.
+    pub fn resume(&mut self) -> bool {
+        if self.state != State::Stopped {
+            return false;
+        }
+        self.state = self.stopped_state;
+        unsafe {
+            HAVE_WORK.set(HAVE_WORK.get() + self.pending_work());
+        }
+        true
+    }
+
+    pub fn restart_count(&self) -> usize {
+        self.restart_count
+    }
+
+    pub fn last_fault(&self) -> Option<FaultInfo> {
+        self.last_fault
+    }
+
+    pub fn restart_deadline(&self) -> Option<(u32, u32)> {
+        self.restart_deadline
+    }
+
+    /// Whether the most recent fault was the stack growing into the guard
+    /// region below it, either by a direct access or while the hardware was
+    /// stacking registers on exception entry.
+    pub fn stack_overflowed(&self) -> bool {
+        let guard_start = self.stack_guard as usize;
+        let guard_end = guard_start + STACK_GUARD_SIZE;
+        match self.last_fault {
+            Some(fault) => {
+                let mstkerr = (fault.cfsr & 0x10) == 0x10;
+                let mmfarvalid = (fault.cfsr & 0x80) == 0x80;
+                let mmfar = fault.mmfar as usize;
+                (mmfarvalid && mmfar >= guard_start && mmfar < guard_end) ||
+                (mstkerr && (self.cur_stack as usize) < guard_end)
+            }
+            None => false,
+        }
+    }
+
     pub fn yield_state(&mut self) {
         if self.state == State::Running {
             self.state = State::Yielded;
@@ -270,26 +872,145 @@ impl<'a> Process<'a> {
 
     pub unsafe fn fault_state(&mut self) {
         write_volatile(&mut APP_FAULT, 0);
+        HAVE_WORK.set(HAVE_WORK.get() - self.pending_work());
         self.state = State::Fault;
+        self.last_fault = Some(FaultInfo::capture());
+        debug::begin_debug_report(|writer| self.fault_report(writer));
 
         match self.fault_response {
             FaultResponse::Panic => {
                 // process faulted. Panic and print status
+                if self.stack_overflowed() {
+                    panic!("Process {} had a fault: stack overflow", self.package_name);
+                }
                 panic!("Process {} had a fault", self.package_name);
             }
-            FaultResponse::Restart => {
-                //XXX: unimplemented
+            FaultResponse::Restart(policy) => {
+                // Drop pending callbacks, which no longer count as work, so
+                // they are not delivered to the restarted process
+                while self.tasks.dequeue().is_some() {}
+
+                if self.restart_count >= policy.max_restarts {
+                    debug!("Process {} faulted after {} restarts, stopping it",
+                           self.package_name,
+                           self.restart_count);
+                    self.reset_containers();
+                    return;
+                }
+
+                // Exponential backoff, capped to keep the shift in range
+                let shift = cmp::min(self.restart_count, 16);
+                let delay_ms = policy.backoff_ms.saturating_mul(1 << shift);
+                self.restart_count += 1;
+
+                match restart::restart_timer() {
+                    Some(timer) if delay_ms > 0 => {
+                        self.reset_containers();
+                        self.restart_deadline = Some((timer.now(), timer.ms_to_tics(delay_ms)));
+                        timer.update();
+                    }
+                    _ => {
+                        if !self.restart() {
+                            panic!("Process {} had a fault and could not be restarted",
+                                   self.package_name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Restarts the process if it is waiting to be restarted and its backoff
+    /// has expired by `now`.
+    pub unsafe fn restart_if_due(&mut self, now: u32) {
+        let due = match self.restart_deadline {
+            Some((t0, delay)) => now.wrapping_sub(t0) >= delay,
+            None => false,
+        };
+        if due {
+            if !self.restart() {
                 panic!("Process {} had a fault and could not be restarted",
                        self.package_name);
-                /*
-                // HAVE_WORK is really screwed up in this case
-                // the tasks ring buffer needs to be cleared
-                // need to re-load() the app
-                 */
             }
         }
     }
 
+    /// Reloads the process from its flash image and queues its entry point.
+    ///
+    /// All pending tasks are dropped, the grant region and container
+    /// pointers are wiped and the GOT, data and BSS are re-initialized from
+    /// flash. Any pending restart of a faulted process is cancelled. Returns
+    /// false if the flash image is no longer valid, or no longer matches its
+    /// hash or signature.
+    pub unsafe fn restart(&mut self) -> bool {
+        let load_info = match parse_and_validate_load_info(self.text.as_ptr()) {
+            Some(load_info) => load_info,
+            None => return false,
+        };
+        if verify_image(self.text.as_ptr(), &load_info).is_err() {
+            return false;
+        }
+
+        // Clear the tasks ring buffer
+        HAVE_WORK.set(HAVE_WORK.get() - self.pending_work());
+        while self.tasks.dequeue().is_some() {}
+        self.restart_deadline = None;
+
+        let load_result = match load(&load_info,
+                                     self.text.as_ptr(),
+                                     self.memory.as_mut_ptr(),
+                                     self.memory.len()) {
+            Ok(load_result) => load_result,
+            Err(_) => {
+                self.state = State::Fault;
+                return false;
+            }
+        };
+
+        let stack_len = align8!(load_info.min_stack_len);
+        let stack_guard = self.memory
+            .as_ptr()
+            .offset(stack_guard_offset(load_result.data_len as usize) as isize);
+        let stack_heap_boundary = stack_guard.offset((STACK_GUARD_SIZE + stack_len as usize) as
+                                                     isize);
+
+        self.app_mem_start = load_result.app_mem_start;
+        self.stack_guard = stack_guard;
+        self.stack_heap_boundary = stack_heap_boundary;
+        self.app_memory_break = stack_heap_boundary;
+        self.cur_stack = stack_heap_boundary;
+
+        self.reset_containers();
+
+        for region in self.mpu_regions.iter() {
+            region.set((ptr::null(), 0));
+        }
+
+        self.syscall_count.set(0);
+        self.last_syscall.set(None);
+        self.cpu_time_us.set(0);
+        self.timeslice_expirations.set(0);
+        self.callbacks_delivered.set(0);
+        self.callbacks_dropped.set(0);
+        self.grant_limit = load_info.grant_limit();
+        self.grant_alloc_failures.set(0);
+        self.stored_regs = Default::default();
+        self.yield_pc = load_result.init_fn;
+        self.psr = 0x01000000;
+        self.state = State::Yielded;
+
+        self.tasks.enqueue(Task::FunctionCall(FunctionCall {
+            pc: load_result.init_fn,
+            r0: load_result.app_mem_start as usize,
+            r1: self.app_memory_break as usize,
+            r2: self.kernel_memory_break as usize,
+            r3: 0,
+        }));
+        HAVE_WORK.set(HAVE_WORK.get() + 1);
+
+        true
+    }
+
     pub fn dequeue_task(&mut self) -> Option<Task> {
         self.tasks.dequeue().map(|cb| {
             unsafe {
@@ -307,50 +1028,84 @@ impl<'a> Process<'a> {
         unsafe { self.memory.as_ptr().offset(self.memory.len() as isize) }
     }
 
+    /// Whether the app's header lets it use driver `driver_num`, and issue
+    /// command `command_num` to it if one is given.
+    ///
+    /// An app whose header has no `Permissions` entry may use every driver,
+    /// and anyone building an image can write whatever entry they like. The
+    /// restrictions therefore only hold against an app when the board
+    /// requires images to be signed, see `set_app_signing_key`.
+    pub fn permitted(&self, driver_num: usize, command_num: Option<usize>) -> bool {
+        match self.permissions {
+            None => true,
+            Some(permissions) => tbf::permits(permissions, driver_num, command_num),
+        }
+    }
+
+    pub fn app_memory_break(&self) -> *const u8 {
+        self.app_memory_break
+    }
+
+    /// Returns the MPU region that protects the grant region when the kernel
+    /// break is at `kernel_memory_break`. It may extend past the end of
+    /// process memory, which the process cannot access anyway, and by part
+    /// of a subregion below the kernel break.
+    fn grant_region(&self, kernel_memory_break: *const u8) -> MpuRegion {
+        mpu_region_covering(kernel_memory_break as usize, self.mem_end() as usize)
+    }
+
     pub fn setup_mpu<MPU: mpu::MPU>(&self, mpu: &MPU) {
         let data_start = self.memory.as_ptr() as usize;
-        let data_len = (32 - self.memory.len().leading_zeros()) as u32;
+        let data_end = data_start + self.memory.len();
+        // `create` lays out process memory so that this always succeeds
+        let data = mpu_region_exact(data_start, data_end).unwrap_or(MpuRegion {
+            base: data_start,
+            len: (32 - self.memory.len().leading_zeros()) as u32,
+            subregions: 0,
+        });
 
         let text_start = self.text.as_ptr() as usize;
         let text_len = ((32 - self.text.len().leading_zeros()) - 2) as u32;
 
-        let mut grant_size = unsafe {
-            self.memory.as_ptr().offset(self.memory.len() as isize) as u32 -
-            (self.kernel_memory_break as u32)
-        };
-        grant_size = closest_power_of_two(grant_size);
-        let grant_base = unsafe {
-            self.memory
-                .as_ptr()
-                .offset(self.memory.len() as isize)
-                .offset(-(grant_size as isize))
-        };
-        let mgrant_size = grant_size.trailing_zeros() - 1;
+        let grant = self.grant_region(self.kernel_memory_break);
 
         // Data segment read/write/execute
         mpu.set_mpu(0,
-                    data_start as u32,
-                    data_len,
+                    data.base as u32,
+                    data.len,
+                    data.subregions,
                     mpu::ExecutePermission::ExecutionPermitted,
                     mpu::AccessPermission::ReadWrite);
         // Text segment read/execute (no write)
         mpu.set_mpu(1,
                     text_start as u32,
                     text_len,
+                    0,
                     mpu::ExecutePermission::ExecutionPermitted,
                     mpu::AccessPermission::ReadOnly);
 
         // Disallow access to grant region
         mpu.set_mpu(2,
-                    grant_base as u32,
-                    mgrant_size,
+                    grant.base as u32,
+                    grant.len,
+                    grant.subregions,
+                    mpu::ExecutePermission::ExecutionNotPermitted,
+                    mpu::AccessPermission::PrivilegedOnly);
+
+        // Disallow access to the stack guard, so that overflowing the stack
+        // faults instead of corrupting the data segment
+        mpu.set_mpu(3,
+                    self.stack_guard as u32,
+                    (STACK_GUARD_SIZE.trailing_zeros() - 1) as u32,
+                    0,
                     mpu::ExecutePermission::ExecutionNotPermitted,
                     mpu::AccessPermission::PrivilegedOnly);
 
         for (i, region) in self.mpu_regions.iter().enumerate() {
-            mpu.set_mpu((i + 3) as u32,
+            mpu.set_mpu((i + 4) as u32,
                         region.get().0 as u32,
                         region.get().1 as u32,
+                        0,
                         mpu::ExecutePermission::ExecutionPermitted,
                         mpu::AccessPermission::ReadWrite);
         }
@@ -375,123 +1130,173 @@ impl<'a> Process<'a> {
         return false;
     }
 
+    /// Removes the MPU region starting at `base` added with `add_mpu_region`.
+    /// Returns whether there was one.
+    pub fn remove_mpu_region(&self, base: *const u8) -> bool {
+        for region in self.mpu_regions.iter() {
+            if region.get().0 != ptr::null() && region.get().0 == base {
+                region.set((ptr::null(), 0));
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Loads the app whose image starts at `app_flash_address` into the
+    /// beginning of `remaining_app_memory`.
+    ///
+    /// Returns the process, or why it could not be loaded, along with the
+    /// number of bytes of flash and of memory it takes up, including any gap
+    /// left to align its memory for the MPU. An app that failed
+    /// to load takes up no memory, but still reports its flash size unless
+    /// its header is invalid, so that the caller can move on to the next app.
     pub unsafe fn create(app_flash_address: *const u8,
                          remaining_app_memory: *mut u8,
                          remaining_app_memory_size: usize,
                          fault_response: FaultResponse)
-                         -> (Option<Process<'a>>, usize, usize) {
-        if let Some(load_info) = parse_and_validate_load_info(app_flash_address) {
-            let app_flash_size = load_info.total_size as usize;
-
-            // Load the process into memory
-            if let Some(load_result) =
-                load(load_info,
-                     app_flash_address,
-                     remaining_app_memory,
-                     remaining_app_memory_size) {
-                let stack_len = align8!(load_info.min_stack_len);
-                let app_heap_len = align8!(load_info.min_app_heap_len);
-                let kernel_heap_len = align8!(load_info.min_kernel_heap_len);
-
-                let app_slice_size =
-                    closest_power_of_two(load_result.data_len + stack_len + app_heap_len +
-                                         kernel_heap_len) as usize;
-                // TODO round app_slice_size up to a closer MPU unit.
-                // This is a very conservative approach that rounds up to power of
-                // two. We should be able to make this closer to what we actually need.
-
-                if app_slice_size > remaining_app_memory_size {
-                    panic!("{:?} failed to load. Insufficient memory. Requested {} have {}",
-                           load_result.package_name,
-                           app_slice_size,
-                           remaining_app_memory_size);
-                }
+                         -> (Result<Process<'a>, LoadError>, usize, usize) {
+        let load_info = match parse_and_validate_load_info(app_flash_address) {
+            Some(load_info) => load_info,
+            None => return (Err(LoadError::NoValidHeader), 0, 0),
+        };
+        let app_flash_size = load_info.total_size as usize;
 
-                let app_memory = slice::from_raw_parts_mut(remaining_app_memory, app_slice_size);
-                let stack_heap_boundary = app_memory.as_mut_ptr()
-                    .offset((load_result.data_len + stack_len) as isize);
-                let app_memory_break = stack_heap_boundary;
-
-                // Set up initial grant region
-                let mut kernel_memory_break = app_memory.as_mut_ptr()
-                    .offset(app_memory.len() as isize);
-
-                // make room for container pointers
-                let pointer_size = mem::size_of::<*const usize>();
-                let num_ctrs = read_volatile(&container::CONTAINER_COUNTER);
-                let container_ptrs_size = num_ctrs * pointer_size;
-                kernel_memory_break = kernel_memory_break.offset(-(container_ptrs_size as isize));
-
-                // set all pointers to null
-                let opts = slice::from_raw_parts_mut(kernel_memory_break as *mut *const usize,
-                                                     num_ctrs);
-                for opt in opts.iter_mut() {
-                    *opt = ptr::null()
-                }
+        if let Err(err) = verify_image(app_flash_address, &load_info) {
+            return (Err(err), app_flash_size, 0);
+        }
 
-                // Allocate memory for callback ring buffer
-                let callback_size = mem::size_of::<Task>();
-                let callback_len = 10;
-                let callback_offset = callback_len * callback_size;
-                kernel_memory_break = kernel_memory_break.offset(-(callback_offset as isize));
-
-                // Set up ring buffer
-                let callback_buf = slice::from_raw_parts_mut(kernel_memory_break as *mut Task,
-                                                             callback_len);
-                let tasks = RingBuffer::new(callback_buf);
-
-                let mut process = Process {
-                    memory: app_memory,
-
-                    kernel_memory_break: kernel_memory_break,
-                    app_memory_break: app_memory_break,
-                    stack_heap_boundary: stack_heap_boundary,
-                    cur_stack: stack_heap_boundary,
-                    app_mem_start: load_result.app_mem_start,
-
-                    syscall_count: Cell::new(0),
-                    last_syscall: Cell::new(None),
-
-                    text: slice::from_raw_parts(app_flash_address, app_flash_size),
-
-                    stored_regs: Default::default(),
-                    yield_pc: load_result.init_fn,
-                    // Set the Thumb bit and clear everything else
-                    psr: 0x01000000,
-
-                    state: State::Yielded,
-                    fault_response: fault_response,
-
-                    mpu_regions: [Cell::new((ptr::null(), 0)),
-                                  Cell::new((ptr::null(), 0)),
-                                  Cell::new((ptr::null(), 0)),
-                                  Cell::new((ptr::null(), 0)),
-                                  Cell::new((ptr::null(), 0))],
-                    tasks: tasks,
-                    package_name: load_result.package_name,
-                };
-
-                if (load_result.init_fn & 0x1) != 1 {
-                    panic!("{:?} process image invalid. \
-                           init_fn address must end in 1 to be Thumb, got {:#X}",
-                           load_result.package_name,
-                           load_result.init_fn);
-                }
+        let data_len = align8!(load_info.bss_mem_offset + load_info.bss_size);
+        let stack_len = align8!(load_info.min_stack_len);
+        let app_heap_len = align8!(load_info.min_app_heap_len);
+        let kernel_heap_len = align8!(load_info.min_kernel_heap_len);
 
-                process.tasks.enqueue(Task::FunctionCall(FunctionCall {
-                    pc: load_result.init_fn,
-                    r0: load_result.app_mem_start as usize,
-                    r1: process.app_memory_break as usize,
-                    r2: process.kernel_memory_break as usize,
-                    r3: 0,
-                }));
+        // The ring buffer holds one fewer element than its backing
+        // slice, so allocate an extra slot.
+        let callback_len = match load_info.callback_queue_len() {
+            len if len <= MAX_CALLBACK_QUEUE_LEN => len + 1,
+            len => return (Err(LoadError::CallbackQueueTooLong(len)), app_flash_size, 0),
+        };
+        let callback_offset = align8!(callback_len * mem::size_of::<Task>());
+
+        // Place the app's memory so that a single MPU region, with some
+        // subregions disabled, covers it exactly. This may leave a gap
+        // before the app.
+        let min_app_memory_len = stack_guard_offset(data_len as usize) + STACK_GUARD_SIZE +
+                                 (stack_len + app_heap_len + kernel_heap_len) as usize +
+                                 callback_offset;
+        let (app_memory_start, app_slice_size) =
+            app_memory_layout(remaining_app_memory as usize, min_app_memory_len);
+        let memory_offset = app_memory_start + app_slice_size - remaining_app_memory as usize;
+
+        if memory_offset > remaining_app_memory_size {
+            return (Err(LoadError::InsufficientMemory {
+                        requested: memory_offset,
+                        available: remaining_app_memory_size,
+                    }),
+                    app_flash_size,
+                    0);
+        }
 
-                HAVE_WORK.set(HAVE_WORK.get() + 1);
+        // Load the process into memory
+        let load_result = match load(&load_info,
+                                     app_flash_address,
+                                     app_memory_start as *mut u8,
+                                     app_slice_size) {
+            Ok(load_result) => load_result,
+            Err(err) => return (Err(err), app_flash_size, 0),
+        };
 
-                return (Some(process), app_flash_size, app_slice_size);
-            }
+        if (load_result.init_fn & 0x1) != 1 {
+            return (Err(LoadError::EntryNotThumb(load_result.init_fn)), app_flash_size, 0);
+        }
+
+        let app_memory = slice::from_raw_parts_mut(app_memory_start as *mut u8, app_slice_size);
+        let stack_guard = app_memory.as_mut_ptr()
+            .offset(stack_guard_offset(load_result.data_len as usize) as isize);
+        let stack_heap_boundary = stack_guard.offset((STACK_GUARD_SIZE + stack_len as usize) as
+                                                     isize);
+        let app_memory_break = stack_heap_boundary;
+
+        // Set up initial grant region
+        let mut kernel_memory_break = app_memory.as_mut_ptr()
+            .offset(app_memory.len() as isize);
+
+        // make room for container pointers
+        let pointer_size = mem::size_of::<*const usize>();
+        let num_ctrs = read_volatile(&container::CONTAINER_COUNTER);
+        let container_ptrs_size = num_ctrs * pointer_size;
+        kernel_memory_break = kernel_memory_break.offset(-(container_ptrs_size as isize));
+
+        // set all pointers to null
+        let opts = slice::from_raw_parts_mut(kernel_memory_break as *mut *const usize,
+                                             num_ctrs);
+        for opt in opts.iter_mut() {
+            *opt = ptr::null()
         }
-        (None, 0, 0)
+
+        // Allocate memory for callback ring buffer
+        kernel_memory_break = kernel_memory_break.offset(-(callback_offset as isize));
+
+        // Set up ring buffer
+        let callback_buf = slice::from_raw_parts_mut(kernel_memory_break as *mut Task,
+                                                     callback_len);
+        let tasks = RingBuffer::new(callback_buf);
+
+        let mut process = Process {
+            memory: app_memory,
+
+            kernel_memory_break: kernel_memory_break,
+            app_memory_break: app_memory_break,
+            stack_heap_boundary: stack_heap_boundary,
+            cur_stack: stack_heap_boundary,
+            app_mem_start: load_result.app_mem_start,
+            stack_guard: stack_guard,
+            grant_start: kernel_memory_break,
+
+            syscall_count: Cell::new(0),
+            last_syscall: Cell::new(None),
+            cpu_time_us: Cell::new(0),
+            timeslice_expirations: Cell::new(0),
+            callbacks_delivered: Cell::new(0),
+            callbacks_dropped: Cell::new(0),
+            grant_limit: load_info.grant_limit(),
+            grant_allocated: 0,
+            grant_alloc_failures: Cell::new(0),
+
+            text: slice::from_raw_parts(app_flash_address, app_flash_size),
+
+            stored_regs: Default::default(),
+            yield_pc: load_result.init_fn,
+            // Set the Thumb bit and clear everything else
+            psr: 0x01000000,
+
+            state: State::Yielded,
+            fault_response: fault_response,
+            restart_count: 0,
+            stopped_state: State::Yielded,
+            last_fault: None,
+            restart_deadline: None,
+
+            mpu_regions: [Cell::new((ptr::null(), 0)),
+                          Cell::new((ptr::null(), 0)),
+                          Cell::new((ptr::null(), 0)),
+                          Cell::new((ptr::null(), 0))],
+            tasks: tasks,
+            package_name: load_result.package_name,
+            permissions: tbf::find_tlv(app_flash_address, tbf::TbfHeaderType::Permissions),
+        };
+
+        process.tasks.enqueue(Task::FunctionCall(FunctionCall {
+            pc: load_result.init_fn,
+            r0: load_result.app_mem_start as usize,
+            r1: process.app_memory_break as usize,
+            r2: process.kernel_memory_break as usize,
+            r3: 0,
+        }));
+
+        HAVE_WORK.set(HAVE_WORK.get() + 1);
+
+        (Ok(process), app_flash_size, memory_offset)
     }
 
     pub fn sbrk(&mut self, increment: isize) -> Result<*const u8, Error> {
@@ -502,7 +1307,9 @@ impl<'a> Process<'a> {
     pub fn brk(&mut self, new_break: *const u8) -> Result<*const u8, Error> {
         if new_break < self.mem_start() || new_break >= self.mem_end() {
             Err(Error::AddressOutOfBounds)
-        } else if new_break > self.kernel_memory_break {
+        } else if new_break as usize > self.grant_region(self.kernel_memory_break).start() {
+            // The MPU region protecting the grant region may extend below
+            // the kernel break
             Err(Error::OutOfMemory)
         } else {
             let old_break = self.app_memory_break;
@@ -518,17 +1325,136 @@ impl<'a> Process<'a> {
         buf_start_addr >= self.mem_start() && buf_end_addr <= self.mem_end()
     }
 
-    pub unsafe fn alloc(&mut self, size: usize) -> Option<&mut [u8]> {
-        let new_break = self.kernel_memory_break.offset(-(size as isize));
-        if new_break < self.app_memory_break {
+    /// Allocates `size` bytes of grant memory for container `container_num`,
+    /// reusing freed memory if a large enough block is available and
+    /// otherwise moving the kernel break down. Allocations are 8-byte
+    /// aligned. Fails if the allocation would take the process past its
+    /// grant limit.
+    pub unsafe fn alloc(&mut self, size: usize, container_num: usize) -> Option<&mut [u8]> {
+        let block_size = grant_header_size() + align8!(size);
+        let within_limit = match self.grant_limit {
+            Some(limit) => self.grant_allocated + block_size <= limit,
+            None => true,
+        };
+        if !within_limit {
+            self.grant_alloc_failures.set(self.grant_alloc_failures.get() + 1);
+            return None;
+        }
+
+        let mut block = self.kernel_memory_break as *mut GrantHeader;
+        while (block as *const u8) < self.grant_start {
+            let header = &mut *block;
+            if header.free && header.size >= block_size {
+                // Split off what is left over if it can hold an allocation
+                if header.size - block_size > grant_header_size() {
+                    let rest = (block as *mut u8).offset(block_size as isize) as *mut GrantHeader;
+                    *rest = GrantHeader {
+                        size: header.size - block_size,
+                        container_num: 0,
+                        free: true,
+                    };
+                    header.size = block_size;
+                }
+                header.container_num = container_num as u16;
+                header.free = false;
+                self.grant_allocated += header.size;
+                let data = (block as *mut u8).offset(grant_header_size() as isize);
+                return Some(slice::from_raw_parts_mut(data, size));
+            }
+            block = (block as *mut u8).offset(header.size as isize) as *mut GrantHeader;
+        }
+
+        let new_break = self.kernel_memory_break.offset(-(block_size as isize));
+        if new_break < self.app_memory_break ||
+           self.grant_region(new_break).start() < self.app_memory_break as usize {
+            self.grant_alloc_failures.set(self.grant_alloc_failures.get() + 1);
             None
         } else {
             self.kernel_memory_break = new_break;
-            Some(slice::from_raw_parts_mut(new_break as *mut u8, size))
+            self.grant_allocated += block_size;
+            *(new_break as *mut GrantHeader) = GrantHeader {
+                size: block_size,
+                container_num: container_num as u16,
+                free: false,
+            };
+            let data = new_break.offset(grant_header_size() as isize) as *mut u8;
+            Some(slice::from_raw_parts_mut(data, size))
+        }
+    }
+
+    /// Frees a grant allocation. Pointers that are not the start of a grant
+    /// allocation, such as those into the app's own memory, are ignored.
+    /// Freed memory at the kernel break is given back to the app.
+    pub unsafe fn free<T>(&mut self, data: *mut T) {
+        let data = data as *const u8;
+        if data < self.kernel_memory_break || data >= self.grant_start {
+            return;
+        }
+
+        let mut block = self.kernel_memory_break as *mut GrantHeader;
+        while (block as *const u8) < self.grant_start {
+            if (block as *const u8).offset(grant_header_size() as isize) == data {
+                if !(*block).free {
+                    (*block).free = true;
+                    self.grant_allocated -= (*block).size;
+                }
+                break;
+            }
+            block = (block as *mut u8).offset((*block).size as isize) as *mut GrantHeader;
+        }
+
+        // Merge runs of free blocks
+        let mut block = self.kernel_memory_break as *mut GrantHeader;
+        while (block as *const u8) < self.grant_start {
+            let next = (block as *mut u8).offset((*block).size as isize) as *mut GrantHeader;
+            if (*block).free && (next as *const u8) < self.grant_start && (*next).free {
+                (*block).size += (*next).size;
+            } else {
+                block = next;
+            }
+        }
+
+        let bottom = self.kernel_memory_break as *const GrantHeader;
+        if self.kernel_memory_break < self.grant_start && (*bottom).free {
+            self.kernel_memory_break = self.kernel_memory_break.offset((*bottom).size as isize);
+        }
+    }
+
+    /// Bytes of grant memory allocated for container `container_num`,
+    /// including allocation headers.
+    pub fn container_usage(&self, container_num: usize) -> usize {
+        let mut usage = 0;
+        let mut block = self.kernel_memory_break as *const GrantHeader;
+        while (block as *const u8) < self.grant_start {
+            let header = unsafe { &*block };
+            if !header.free && header.container_num as usize == container_num {
+                usage += header.size;
+            }
+            block = unsafe { (block as *const u8).offset(header.size as isize) } as
+                    *const GrantHeader;
         }
+        usage
     }
 
-    pub unsafe fn free<T>(&mut self, _: *mut T) {}
+    /// Drops all of the process's grant allocations and empties its
+    /// containers, so the next access to a container allocates it afresh.
+    /// Capsules registered with `container::add_reset_client` are notified
+    /// beforehand, while the containers still hold the process's state.
+    pub unsafe fn reset_containers(&mut self) {
+        let this = self as *const Process;
+        let index = PROCS.iter().position(|p| match *p {
+            Some(ref p) => p as *const Process == this,
+            None => false,
+        });
+        index.map(|index| container::notify_reset(AppId::new(index)));
+
+        self.kernel_memory_break = self.grant_start;
+        self.grant_allocated = 0;
+        let num_ctrs = read_volatile(&container::CONTAINER_COUNTER);
+        for i in 0..num_ctrs {
+            *self.container_ptr::<usize>(i) = ptr::null_mut();
+        }
+    }
 
     unsafe fn container_ptr<T>(&self, container_num: usize) -> *mut *mut T {
         let container_num = container_num as isize;
@@ -544,9 +1470,9 @@ impl<'a> Process<'a> {
                                                      -> Option<*mut T> {
         let ctr_ptr = self.container_ptr::<T>(container_num);
         if (*ctr_ptr).is_null() {
-            self.alloc(mem::size_of::<T>()).map(|root_arr| {
+            self.alloc(mem::size_of::<T>(), container_num).map(|root_arr| {
                 let root_ptr = root_arr.as_mut_ptr() as *mut T;
-                *root_ptr = Default::default();
+                ptr::write(root_ptr, Default::default());
                 write_volatile(ctr_ptr, root_ptr);
                 root_ptr
             })
@@ -568,6 +1494,7 @@ impl<'a> Process<'a> {
     /// Context switch to the process.
     pub unsafe fn push_function_call(&mut self, callback: FunctionCall) {
         HAVE_WORK.set(HAVE_WORK.get() + 1);
+        self.callbacks_delivered.set(self.callbacks_delivered.get() + 1);
 
         self.state = State::Running;
         // Fill in initial stack expected by SVC handler
@@ -627,6 +1554,30 @@ impl<'a> Process<'a> {
         self.last_syscall.set(self.svc_number());
     }
 
+    pub fn add_cpu_time(&self, us: u32) {
+        self.cpu_time_us.set(self.cpu_time_us.get() + us as usize);
+    }
+
+    pub fn incr_timeslice_expirations(&self) {
+        self.timeslice_expirations.set(self.timeslice_expirations.get() + 1);
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            state: self.state,
+            syscall_count: self.syscall_count.get(),
+            cpu_time_us: self.cpu_time_us.get(),
+            timeslice_expirations: self.timeslice_expirations.get(),
+            callbacks_delivered: self.callbacks_delivered.get(),
+            callbacks_dropped: self.callbacks_dropped.get(),
+            grant_bytes_used: self.mem_end() as usize - self.kernel_memory_break as usize,
+            grant_bytes_allocated: self.grant_allocated,
+            grant_limit: self.grant_limit,
+            grant_alloc_failures: self.grant_alloc_failures.get(),
+            restart_count: self.restart_count,
+        }
+    }
+
     pub fn sp(&self) -> usize {
         self.cur_stack as usize
     }
@@ -646,9 +1597,18 @@ impl<'a> Process<'a> {
         unsafe { read_volatile(pspr) }
     }
 
+    /// Sets the values a system call returns. r0 holds the return code; a
+    /// `SuccessWithValues` also sets r1-r3 to its values, which are otherwise
+    /// left as the process passed them.
     pub fn set_return_code(&mut self, return_code: ReturnCode) {
         let r: isize = return_code.into();
         self.set_r0(r);
+        if let ReturnCode::SuccessWithValues { values } = return_code {
+            let pspr = self.cur_stack as *mut usize;
+            for (i, value) in values.iter().enumerate() {
+                unsafe { write_volatile(pspr.offset(i as isize + 1), *value) }
+            }
+        }
     }
 
     pub fn set_r0(&mut self, val: isize) {
@@ -676,152 +1636,91 @@ impl<'a> Process<'a> {
         unsafe { read_volatile(pspr.offset(3)) }
     }
 
+    /// Writes the report of the process's most recent fault under its own
+    /// heading, as captured when it faulted.
     pub unsafe fn fault_str<W: Write>(&mut self, writer: &mut W) {
-        let _ccr = SCB_REGISTERS[0];
-        let cfsr = SCB_REGISTERS[1];
-        let hfsr = SCB_REGISTERS[2];
-        let mmfar = SCB_REGISTERS[3];
-        let bfar = SCB_REGISTERS[4];
-
-        let iaccviol = (cfsr & 0x01) == 0x01;
-        let daccviol = (cfsr & 0x02) == 0x02;
-        let munstkerr = (cfsr & 0x08) == 0x08;
-        let mstkerr = (cfsr & 0x10) == 0x10;
-        let mlsperr = (cfsr & 0x20) == 0x20;
-        let mmfarvalid = (cfsr & 0x80) == 0x80;
-
-        let ibuserr = ((cfsr >> 8) & 0x01) == 0x01;
-        let preciserr = ((cfsr >> 8) & 0x02) == 0x02;
-        let impreciserr = ((cfsr >> 8) & 0x04) == 0x04;
-        let unstkerr = ((cfsr >> 8) & 0x08) == 0x08;
-        let stkerr = ((cfsr >> 8) & 0x10) == 0x10;
-        let lsperr = ((cfsr >> 8) & 0x20) == 0x20;
-        let bfarvalid = ((cfsr >> 8) & 0x80) == 0x80;
-
-        let undefinstr = ((cfsr >> 16) & 0x01) == 0x01;
-        let invstate = ((cfsr >> 16) & 0x02) == 0x02;
-        let invpc = ((cfsr >> 16) & 0x04) == 0x04;
-        let nocp = ((cfsr >> 16) & 0x08) == 0x08;
-        let unaligned = ((cfsr >> 16) & 0x100) == 0x100;
-        let divbysero = ((cfsr >> 16) & 0x200) == 0x200;
-
-        let vecttbl = (hfsr & 0x02) == 0x02;
-        let forced = (hfsr & 0x40000000) == 0x40000000;
-
-
-        let _ = writer.write_fmt(format_args!("\r\n---| Fault Status |---\r\n"));
-
-        if iaccviol {
-            let _ =
-                writer.write_fmt(format_args!("Instruction Access Violation:       {}\r\n",
-                                              iaccviol));
-        }
-        if daccviol {
-            let _ =
-                writer.write_fmt(format_args!("Data Access Violation:              {}\r\n",
-                                              daccviol));
-        }
-        if munstkerr {
-            let _ =
-                writer.write_fmt(format_args!("Memory Management Unstacking Fault: {}\r\n",
-                                              munstkerr));
-        }
-        if mstkerr {
-            let _ = writer.write_fmt(format_args!("Memory Management Stacking Fault:   {}\r\n",
-                                                  mstkerr));
-        }
-        if mlsperr {
-            let _ = writer.write_fmt(format_args!("Memory Management Lazy FP Fault:    {}\r\n",
-                                                  mlsperr));
-        }
-
-        if ibuserr {
-            let _ = writer.write_fmt(format_args!("Instruction Bus Error:              {}\r\n",
-                                                  ibuserr));
-        }
-        if preciserr {
-            let _ =
-                writer.write_fmt(format_args!("Precise Data Bus Error:             {}\r\n",
-                                              preciserr));
-        }
-        if impreciserr {
-            let _ =
-                writer.write_fmt(format_args!("Imprecise Data Bus Error:           {}\r\n",
-                                              impreciserr));
-        }
-        if unstkerr {
-            let _ =
-                writer.write_fmt(format_args!("Bus Unstacking Fault:               {}\r\n",
-                                              unstkerr));
-        }
-        if stkerr {
-            let _ = writer.write_fmt(format_args!("Bus Stacking Fault:                 {}\r\n",
-                                                  stkerr));
-        }
-        if lsperr {
-            let _ = writer.write_fmt(format_args!("Bus Lazy FP Fault:                  {}\r\n",
-                                                  lsperr));
-        }
+        let _ = writer.write_fmt(format_args!("\r\n---| Process Fault |---\r\n"));
+        self.fault_report(writer);
+    }
 
-        if undefinstr {
-            let _ =
-                writer.write_fmt(format_args!("Undefined Instruction Usage Fault:  {}\r\n",
-                                              undefinstr));
-        }
-        if invstate {
-            let _ =
-                writer.write_fmt(format_args!("Invalid State Usage Fault:          {}\r\n",
-                                              invstate));
-        }
-        if invpc {
-            let _ =
-                writer.write_fmt(format_args!("Invalid PC Load Usage Fault:        {}\r\n", invpc));
-        }
-        if nocp {
-            let _ =
-                writer.write_fmt(format_args!("No Coprocessor Usage Fault:         {}\r\n", nocp));
-        }
-        if unaligned {
-            let _ =
-                writer.write_fmt(format_args!("Unaligned Access Usage Fault:       {}\r\n",
-                                              unaligned));
+    /// Writes a report of the most recent fault: its decoded causes, the
+    /// registers stacked by the hardware on exception entry, the process
+    /// memory layout and the last system call.
+    pub unsafe fn fault_report<W: Write>(&self, writer: &mut W) {
+        let _ = writer.write_fmt(format_args!("Process {} faulted (restarts: {})\r\n",
+                                              self.package_name,
+                                              self.restart_count));
+        match self.last_fault {
+            Some(fault) => fault.write_status(writer),
+            None => {
+                let _ = writer.write_fmt(format_args!("No fault status captured.\r\n"));
+            }
         }
-        if divbysero {
-            let _ =
-                writer.write_fmt(format_args!("Divide By Zero:                     {}\r\n",
-                                              divbysero));
+        if self.stack_overflowed() {
+            let _ = writer.write_fmt(format_args!("Cause: stack overflow\r\n"));
         }
 
-        if vecttbl {
-            let _ = writer.write_fmt(format_args!("Bus Fault on Vector Table Read:     {}\r\n",
-                                                  vecttbl));
-        }
-        if forced {
-            let _ = writer.write_fmt(format_args!("Forced Hard Fault:                  {}\r\n",
-                                                  forced));
+        // A stacking fault can leave the stack pointer outside the process,
+        // so only trust the stacked registers if they are in its memory.
+        if self.in_exposed_bounds(self.cur_stack, 8 * mem::size_of::<usize>()) {
+            let pspr = self.cur_stack as *const usize;
+            let _ = writer.write_fmt(format_args!("\
+                R0 : {:#010X}  R1 : {:#010X}  R2 : {:#010X}  R3 : {:#010X}\r\n\
+                R12: {:#010X}  LR : {:#010X}  PC : {:#010X}  PSR: {:#010X}\r\n",
+                                                  read_volatile(pspr),
+                                                  read_volatile(pspr.offset(1)),
+                                                  read_volatile(pspr.offset(2)),
+                                                  read_volatile(pspr.offset(3)),
+                                                  read_volatile(pspr.offset(4)),
+                                                  read_volatile(pspr.offset(5)),
+                                                  read_volatile(pspr.offset(6)),
+                                                  read_volatile(pspr.offset(7))));
+        } else {
+            let _ = writer.write_fmt(format_args!("Stacked registers unavailable.\r\n"));
         }
 
-        if mmfarvalid {
-            let _ =
-                writer.write_fmt(format_args!("Faulting Memory Address:            {:#010X}\r\n",
-                                              mmfar));
-        }
-        if bfarvalid {
-            let _ =
-                writer.write_fmt(format_args!("Bus Fault Address:                  {:#010X}\r\n",
-                                              bfar));
-        }
+        let _ = writer.write_fmt(format_args!("\
+            SP : {:#010X}\r\n\
+            app_mem_start:       {:#010X}\r\n\
+            stack_guard:         {:#010X}\r\n\
+            stack_heap_boundary: {:#010X}\r\n\
+            app_memory_break:    {:#010X}\r\n\
+            kernel_memory_break: {:#010X}\r\n",
+                                              self.cur_stack as usize,
+                                              self.app_mem_start as usize,
+                                              self.stack_guard as usize,
+                                              self.stack_heap_boundary as usize,
+                                              self.app_memory_break as usize,
+                                              self.kernel_memory_break as usize));
+
+        let _ = match self.last_syscall.get() {
+            Some(syscall) => writer.write_fmt(format_args!("Last Syscall: {:?}\r\n", syscall)),
+            None => writer.write_fmt(format_args!("Last Syscall: None\r\n")),
+        };
+    }
 
-        if cfsr == 0 && hfsr == 0 {
-            let _ = writer.write_fmt(format_args!("No faults detected.\r\n"));
-        } else {
-            let _ =
-                writer.write_fmt(format_args!("Fault Status Register (CFSR):       {:#010X}\r\n",
-                                              cfsr));
-            let _ =
-                writer.write_fmt(format_args!("Hard Fault Status Register (HFSR):  {:#010X}\r\n",
-                                              hfsr));
-        }
+    /// Writes one line for each task waiting in the process's queue, oldest
+    /// first.
+    pub fn tasks_str<W: Write>(&self, writer: &mut W) {
+        let _ = writer.write_fmt(format_args!(" Queued Tasks: {}\r\n", self.tasks.len()));
+        self.tasks.for_each(|task| {
+            let _ = match task {
+                Task::FunctionCall(call) => {
+                    writer.write_fmt(format_args!("   Callback PC: {:#010X}  R0: {:#010X}  \
+                                                   R1: {:#010X}  R2: {:#010X}  R3: {:#010X}\r\n",
+                                                  call.pc,
+                                                  call.r0,
+                                                  call.r1,
+                                                  call.r2,
+                                                  call.r3))
+                }
+                Task::IPC((from, cb_type)) => {
+                    writer.write_fmt(format_args!("   IPC {:?} from app {}\r\n",
+                                                  cb_type,
+                                                  from.idx()))
+                }
+            };
+        });
     }
 
     pub unsafe fn statistics_str<W: Write>(&mut self, writer: &mut W) {
@@ -843,7 +1742,8 @@ impl<'a> Process<'a> {
             let flash_data_size = load_info.got_size + load_info.data_size +
                                   load_info.pkg_name_size;
             let flash_text_size = load_info.text_size;
-            let flash_header_size = mem::size_of::<LoadInfo>() + load_info.rel_data_size as usize;
+            // The header and relocation data precede the text segment
+            let flash_header_size = load_info.text_offset as usize;
 
             // SRAM addresses
             let sram_end = self.memory.as_ptr().offset(self.memory.len() as isize) as usize;
@@ -880,6 +1780,7 @@ impl<'a> Process<'a> {
 
             // application statistics
             let events_queued = self.tasks.len();
+            let events_dropped = self.callbacks_dropped.get();
             let syscall_count = self.syscall_count.get();
             let last_syscall = self.last_syscall.get();
 
@@ -904,10 +1805,11 @@ impl<'a> Process<'a> {
             // You can thank the piece of garbage rustfmt for this.
             let _ = writer.write_fmt(format_args!("\
             App: {}   -   [{:?}]\
-            \r\n Events Queued: {}   Syscall Count: {}   ",
+            \r\n Events Queued: {}   Events Dropped: {}   Syscall Count: {}   ",
                                                   self.package_name,
                                                   self.state,
                                                   events_queued,
+                                                  events_dropped,
                                                   syscall_count,
                                                   ));
 
@@ -916,6 +1818,24 @@ impl<'a> Process<'a> {
                 None => writer.write_fmt(format_args!("Last Syscall: None")),
             };
 
+            let _ = writer.write_fmt(format_args!("\r\n Grant Allocated: {}   ",
+                                                  self.grant_allocated));
+            let _ = match self.grant_limit {
+                Some(limit) => writer.write_fmt(format_args!("Grant Limit: {}   ", limit)),
+                None => writer.write_fmt(format_args!("Grant Limit: None   ")),
+            };
+            let _ = writer.write_fmt(format_args!("Failed Allocations: {}",
+                                                  self.grant_alloc_failures.get()));
+            let num_ctrs = read_volatile(&container::CONTAINER_COUNTER);
+            for i in 0..num_ctrs {
+                let usage = self.container_usage(i);
+                if usage > 0 {
+                    let _ = writer.write_fmt(format_args!("\r\n   Container {:2}: {} bytes",
+                                                          i,
+                                                          usage));
+                }
+            }
+
             let _ = writer.write_fmt(format_args!("\
             \r\n\
             \r\n ╔═══════════╤══════════════\
@@ -1038,6 +1958,23 @@ struct LoadResult {
     package_name: &'static str,
 }
 
+/// Checks the image at `app_flash_address` against the hash in its header
+/// and, if the board requires signed apps, against its signature. Every time
+/// an image is loaded, including on restart, it goes through this check.
+unsafe fn verify_image(app_flash_address: *const u8,
+                       load_info: &LoadInfo)
+                       -> Result<(), LoadError> {
+    if !tbf::verify_sha256(app_flash_address, load_info) {
+        return Err(LoadError::HashMismatch);
+    }
+    if let Some(key) = APP_SIGNING_KEY {
+        if !tbf::verify_signature(app_flash_address, load_info, key) {
+            return Err(LoadError::BadSignature);
+        }
+    }
+    Ok(())
+}
+
 /// Loads the process into memory
 ///
 /// Loads the process whos binary starts at `flash_start_addr` into the memory
@@ -1049,12 +1986,12 @@ struct LoadResult {
 /// variables named in the relocation section of the binary.
 ///
 /// The function returns a `LoadResult` containing metadata about the loaded
-/// process or None if loading failed.
-unsafe fn load(load_info: &'static LoadInfo,
+/// process, or an error if the data, GOT and BSS do not fit in `mem_size`.
+unsafe fn load(load_info: &LoadInfo,
                flash_start_addr: *const u8,
                mem_base: *mut u8,
                mem_size: usize)
-               -> Option<LoadResult> {
+               -> Result<LoadResult, LoadError> {
     let mem_end = mem_base.offset(mem_size as isize);
 
     let package_name_byte_array =
@@ -1091,13 +2028,10 @@ unsafe fn load(load_info: &'static LoadInfo,
 
     // Verify target data fits in memory
     if target_data.len() > mem_size {
-        // When a kernel warning mechanism exists, this panic should be
-        // replaced with that, but for now it seems more useful to bail out to
-        // alert developers of why the app failed to load
-        panic!("{:?} failed to load. Data + GOT ({}) exceeded available memory ({})",
-               load_result.package_name,
-               target_data.len(),
-               mem_size);
+        return Err(LoadError::InsufficientMemory {
+            requested: target_data.len(),
+            available: mem_size,
+        });
     }
 
     // Copy the GOT and data into base memory
@@ -1108,8 +2042,10 @@ unsafe fn load(load_info: &'static LoadInfo,
     // Zero out BSS
     let bss = mem_base.offset(load_info.bss_mem_offset as isize);
     if bss.offset(load_info.bss_size as isize) > mem_end {
-        panic!("{:?} failed to load. BSS overran available memory",
-               load_result.package_name);
+        return Err(LoadError::InsufficientMemory {
+            requested: load_info.bss_mem_offset as usize + load_info.bss_size as usize,
+            available: mem_size,
+        });
     }
     intrinsics::write_bytes(mem_base.offset(load_info.bss_mem_offset as isize),
                             0,
@@ -1152,5 +2088,111 @@ unsafe fn load(load_info: &'static LoadInfo,
     load_result.app_mem_start = mem_base.offset(aligned_mem_start as isize);
     load_result.data_len = aligned_mem_start;
 
-    Some(load_result)
+    Ok(load_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MPU_MIN_REGION_SIZE, STACK_GUARD_SIZE, app_memory_layout, mpu_region_covering,
+                mpu_region_exact, stack_guard_offset};
+
+    /// Size in bytes of a region with the given encoded length.
+    fn region_size(len: u32) -> usize {
+        1 << (len + 1)
+    }
+
+    #[test]
+    fn stack_guard_follows_data() {
+        assert_eq!(stack_guard_offset(0), 0);
+        assert_eq!(stack_guard_offset(1), STACK_GUARD_SIZE);
+        assert_eq!(stack_guard_offset(STACK_GUARD_SIZE), STACK_GUARD_SIZE);
+        assert_eq!(stack_guard_offset(STACK_GUARD_SIZE + 1), 2 * STACK_GUARD_SIZE);
+        for data_len in 0..200 {
+            let offset = stack_guard_offset(data_len);
+            assert_eq!(offset % STACK_GUARD_SIZE, 0);
+            assert!(offset >= data_len && offset < data_len + STACK_GUARD_SIZE);
+        }
+    }
+
+    #[test]
+    fn exact_small_region() {
+        // Regions below 256 bytes have no subregions
+        let region = mpu_region_exact(0x1000, 0x1020).unwrap();
+        assert_eq!(region.base, 0x1000);
+        assert_eq!(region_size(region.len), 32);
+        assert_eq!(region.subregions, 0);
+    }
+
+    #[test]
+    fn exact_region_disables_trailing_subregions() {
+        let region = mpu_region_exact(0x2000, 0x2300).unwrap();
+        assert_eq!(region.base, 0x2000);
+        assert_eq!(region_size(region.len), 1024);
+        assert_eq!(region.subregions, 0xc0);
+        assert_eq!(region.start(), 0x2000);
+    }
+
+    #[test]
+    fn exact_region_disables_leading_subregions() {
+        let region = mpu_region_exact(0x2100, 0x2400).unwrap();
+        assert_eq!(region.base, 0x2000);
+        assert_eq!(region_size(region.len), 1024);
+        assert_eq!(region.subregions, 0x03);
+        assert_eq!(region.start(), 0x2100);
+    }
+
+    #[test]
+    fn exact_region_needs_subregion_alignment() {
+        assert!(mpu_region_exact(0x1000, 0x1010).is_none());
+        assert!(mpu_region_exact(0x2010, 0x2100).is_none());
+    }
+
+    #[test]
+    fn covering_region_covers_range() {
+        for &(start, end) in [(0x1000, 0x1001), (0x2010, 0x2100), (0x2100, 0x2400),
+                              (0x20000f00, 0x20001100), (0x3fe0, 0x4020)]
+            .iter() {
+            let region = mpu_region_covering(start, end);
+            assert_eq!(region.base % region_size(region.len), 0);
+            assert!(region.start() <= start);
+            assert!(region.base + region_size(region.len) >= end);
+        }
+    }
+
+    #[test]
+    fn covering_region_starts_close_to_range() {
+        let region = mpu_region_covering(0x2010, 0x2100);
+        assert_eq!(region.base, 0x2000);
+        assert_eq!(region_size(region.len), 256);
+        assert_eq!(region.subregions, 0);
+
+        // Subregions below the range are disabled
+        let region = mpu_region_covering(0x2100, 0x2400);
+        assert_eq!(region.start(), 0x2100);
+        assert_eq!(region.subregions, 0x03);
+    }
+
+    #[test]
+    fn layout_rounds_to_region_size() {
+        assert_eq!(app_memory_layout(0x20000000, 100), (0x20000000, 128));
+        assert_eq!(app_memory_layout(0x20000100, 0x700), (0x20000100, 0x700));
+        let region = mpu_region_exact(0x20000100, 0x20000800).unwrap();
+        assert_eq!(region.base, 0x20000000);
+        assert_eq!(region.subregions, 0x01);
+    }
+
+    #[test]
+    fn layout_fits_an_exact_region() {
+        for &start in [0x20000000, 0x20000020, 0x20000100, 0x20000a40, 0x20003fe0].iter() {
+            for &min_len in [1, 32, 100, 256, 1000, 3000, 5000, 0x4100].iter() {
+                let (app_start, app_len) = app_memory_layout(start, min_len);
+                assert!(app_start >= start);
+                assert!(app_len >= min_len);
+                // The stack guard must be aligned to its own size
+                assert_eq!(app_start % MPU_MIN_REGION_SIZE, 0);
+                let region = mpu_region_exact(app_start, app_start + app_len).unwrap();
+                assert_eq!(region.start(), app_start);
+            }
+        }
+    }
 }
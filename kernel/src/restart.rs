@@ -0,0 +1,94 @@
+//! Delayed restarts of faulted processes.
+//!
+//! A process whose `FaultResponse` is `Restart` with a non-zero backoff stays
+//! in the `Fault` state until its backoff expires. Boards provide an alarm,
+//! wrapped in a `RestartAlarm`, that wakes the kernel to reload those
+//! processes.
+
+use hil::time::{self, Alarm, Frequency};
+use process;
+
+/// A time source the kernel uses to schedule process restarts.
+pub trait RestartTimer {
+    /// Returns the current time in tics.
+    fn now(&self) -> u32;
+
+    /// Converts a delay in milliseconds to tics, saturating on overflow.
+    fn ms_to_tics(&self, ms: u32) -> u32;
+
+    /// Arms the timer for the nearest pending restart, or disables it if no
+    /// restart is pending.
+    fn update(&self);
+}
+
+static mut RESTART_TIMER: Option<&'static RestartTimer> = None;
+
+/// Registers the timer used for delayed restarts. Without one, faulted
+/// processes are restarted immediately regardless of their backoff.
+pub unsafe fn set_restart_timer(timer: &'static RestartTimer) {
+    RESTART_TIMER = Some(timer);
+}
+
+pub fn restart_timer() -> Option<&'static RestartTimer> {
+    unsafe { RESTART_TIMER }
+}
+
+/// `RestartTimer` backed by a (typically virtualized) alarm.
+pub struct RestartAlarm<'a, A: Alarm + 'a> {
+    alarm: &'a A,
+}
+
+impl<'a, A: Alarm> RestartAlarm<'a, A> {
+    pub const fn new(alarm: &'a A) -> RestartAlarm<'a, A> {
+        RestartAlarm { alarm: alarm }
+    }
+}
+
+impl<'a, A: Alarm> RestartTimer for RestartAlarm<'a, A> {
+    fn now(&self) -> u32 {
+        self.alarm.now()
+    }
+
+    fn ms_to_tics(&self, ms: u32) -> u32 {
+        let tics = (ms as u64) * (<A::Frequency>::frequency() as u64) / 1000;
+        if tics > u32::max_value() as u64 {
+            u32::max_value()
+        } else {
+            tics as u32
+        }
+    }
+
+    fn update(&self) {
+        let now = self.alarm.now();
+        let mut next_alarm = None;
+        let mut next_dist = u32::max_value();
+        unsafe {
+            for p in process::PROCS.iter().filter_map(|p| p.as_ref()) {
+                p.restart_deadline().map(|(t0, delay)| {
+                    let t_alarm = t0.wrapping_add(delay);
+                    let t_dist = t_alarm.wrapping_sub(now);
+                    if next_alarm.is_none() || next_dist > t_dist {
+                        next_alarm = Some(t_alarm);
+                        next_dist = t_dist;
+                    }
+                });
+            }
+        }
+        match next_alarm {
+            Some(t_alarm) => self.alarm.set_alarm(t_alarm),
+            None => self.alarm.disable(),
+        }
+    }
+}
+
+impl<'a, A: Alarm> time::Client for RestartAlarm<'a, A> {
+    fn fired(&self) {
+        let now = self.alarm.now();
+        unsafe {
+            for p in process::PROCS.iter_mut().filter_map(|p| p.as_mut()) {
+                p.restart_if_due(now);
+            }
+        }
+        self.update();
+    }
+}
@@ -7,6 +7,7 @@
 #[derive(Clone, Copy, PartialEq)]
 pub enum ReturnCode {
     SuccessWithValue { value: usize }, // Success value must be positive
+    SuccessWithValues { values: [usize; 3] }, // Success, values returned in r1-r3
     SUCCESS,
     FAIL, //.......... Generic failure condition
     EBUSY, //......... Underlying system is busy; retry
@@ -19,12 +20,14 @@ pub enum ReturnCode {
     ENOMEM, //........ Memory required not available
     ENOSUPPORT, //.... Operation or command is unsupported
     ENODEVICE, //..... Device does not exist
+    EPERM, //......... The app is not permitted to use the driver
 }
 
 impl From<ReturnCode> for isize {
     fn from(original: ReturnCode) -> isize {
         match original {
             ReturnCode::SuccessWithValue { value } => value as isize,
+            ReturnCode::SuccessWithValues { .. } => 0,
             ReturnCode::SUCCESS => 0,
             ReturnCode::FAIL => -1,
             ReturnCode::EBUSY => -2,
@@ -37,6 +40,7 @@ impl From<ReturnCode> for isize {
             ReturnCode::ENOMEM => -9,
             ReturnCode::ENOSUPPORT => -10,
             ReturnCode::ENODEVICE => -11,
+            ReturnCode::EPERM => -12,
         }
     }
 }
@@ -46,3 +50,60 @@ impl From<ReturnCode> for usize {
         isize::from(original) as usize
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ReturnCode;
+
+    const ERRORS: [(ReturnCode, isize); 12] = [(ReturnCode::FAIL, -1),
+                                               (ReturnCode::EBUSY, -2),
+                                               (ReturnCode::EALREADY, -3),
+                                               (ReturnCode::EOFF, -4),
+                                               (ReturnCode::ERESERVE, -5),
+                                               (ReturnCode::EINVAL, -6),
+                                               (ReturnCode::ESIZE, -7),
+                                               (ReturnCode::ECANCEL, -8),
+                                               (ReturnCode::ENOMEM, -9),
+                                               (ReturnCode::ENOSUPPORT, -10),
+                                               (ReturnCode::ENODEVICE, -11),
+                                               (ReturnCode::EPERM, -12)];
+
+    #[test]
+    fn success_is_zero() {
+        assert_eq!(isize::from(ReturnCode::SUCCESS), 0);
+        assert_eq!(usize::from(ReturnCode::SUCCESS), 0);
+    }
+
+    #[test]
+    fn success_with_value_is_the_value() {
+        assert_eq!(isize::from(ReturnCode::SuccessWithValue { value: 0 }), 0);
+        assert_eq!(isize::from(ReturnCode::SuccessWithValue { value: 42 }), 42);
+        assert_eq!(usize::from(ReturnCode::SuccessWithValue { value: 42 }), 42);
+    }
+
+    #[test]
+    fn success_with_values_is_zero() {
+        // The values themselves are returned in r1-r3
+        let code = ReturnCode::SuccessWithValues { values: [1, 2, 3] };
+        assert_eq!(isize::from(code), 0);
+        assert_eq!(usize::from(code), 0);
+    }
+
+    #[test]
+    fn errors_are_negative() {
+        for &(code, expected) in ERRORS.iter() {
+            assert_eq!(isize::from(code), expected);
+            assert_eq!(usize::from(code), expected as usize);
+        }
+    }
+
+    #[test]
+    fn errors_are_distinct() {
+        for (i, &(a, _)) in ERRORS.iter().enumerate() {
+            for &(b, _) in ERRORS[i + 1..].iter() {
+                assert!(a != b);
+                assert!(isize::from(a) != isize::from(b));
+            }
+        }
+    }
+}
@@ -10,23 +10,50 @@ pub unsafe fn do_process<P: Platform, C: Chip>(platform: &P,
                                                chip: &mut C,
                                                process: &mut Process,
                                                appid: ::AppId,
-                                               ipc: &::ipc::IPC) {
+                                               ipc: &::ipc::IPC,
+                                               timeslice: Option<u32>) {
     let systick = chip.systick();
     systick.reset();
-    systick.set_timer(10000);
-    systick.enable(true);
+    let preemptive = timeslice.is_some();
+    timeslice.map(|us| {
+        systick.set_timer(us);
+        systick.enable(true);
+    });
+
+    // Reading the overflow flag clears it, so remember when it was seen
+    let mut expired = false;
 
     loop {
-        if chip.has_pending_interrupts() || systick.overflowed() || systick.value() <= 500 {
+        if chip.has_pending_interrupts() {
+            break;
+        }
+        if preemptive && (expired || systick.overflowed() || systick.value() <= 500) {
+            if process.current_state() == process::State::Running {
+                process.incr_timeslice_expirations();
+            }
             break;
         }
 
         match process.current_state() {
             process::State::Running => {
                 process.setup_mpu(chip.mpu());
-                systick.enable(true);
+                if preemptive {
+                    systick.enable(true);
+                }
+                let time_left = systick.value();
                 process.switch_to();
                 systick.enable(false);
+                if preemptive {
+                    // Once the systick overflows, whatever was left of the
+                    // timeslice has been used up
+                    expired = systick.overflowed();
+                    let used = if expired {
+                        time_left
+                    } else {
+                        time_left.saturating_sub(systick.value())
+                    };
+                    process.add_cpu_time(used);
+                }
             }
             process::State::Yielded => {
                 match process.dequeue_task() {
@@ -45,8 +72,13 @@ pub unsafe fn do_process<P: Platform, C: Chip>(platform: &P,
                 }
             }
             process::State::Fault => {
-                // we should never be scheduling a process in fault
-                panic!("Attempted to schedule a faulty process");
+                // The process is either waiting out its restart backoff or
+                // has been stopped for good
+                break;
+            }
+            process::State::Stopped => {
+                // Stopped on request, nothing runs until it is resumed
+                break;
             }
         }
 
@@ -97,42 +129,57 @@ pub unsafe fn do_process<P: Platform, C: Chip>(platform: &P,
                 let callback_ptr_raw = process.r2() as *mut ();
                 let appdata = process.r3();
 
-                let res = if callback_ptr_raw as usize == 0 {
-                    ReturnCode::EINVAL
+                let res = if !process.permitted(driver_num, None) {
+                    ReturnCode::EPERM
                 } else {
-                    let callback_ptr = NonZero::new(callback_ptr_raw);
-
-                    let callback = ::Callback::new(appid, appdata, callback_ptr);
+                    // A null callback revokes the current one
+                    let callback = if callback_ptr_raw as usize == 0 {
+                        None
+                    } else {
+                        let callback_ptr = NonZero::new(callback_ptr_raw);
+                        Some(::Callback::new(appid, appdata, callback_ptr))
+                    };
                     platform.with_driver(driver_num, |driver| match driver {
-                        Some(d) => d.subscribe(subdriver_num, callback),
+                        Some(d) => d.subscribe(subdriver_num, callback, appid),
                         None => ReturnCode::ENODEVICE,
                     })
                 };
                 process.set_return_code(res);
             }
             Some(Syscall::COMMAND) => {
-                let res = platform.with_driver(process.r0(), |driver| match driver {
-                    Some(d) => d.command(process.r1(), process.r2(), appid),
-                    None => ReturnCode::ENODEVICE,
-                });
+                let res = if !process.permitted(process.r0(), Some(process.r1())) {
+                    ReturnCode::EPERM
+                } else {
+                    platform.with_driver(process.r0(), |driver| match driver {
+                        Some(d) => d.command(process.r1(), process.r2(), appid),
+                        None => ReturnCode::ENODEVICE,
+                    })
+                };
                 process.set_return_code(res);
             }
             Some(Syscall::ALLOW) => {
-                let res = platform.with_driver(process.r0(), |driver| {
-                    match driver {
-                        Some(d) => {
-                            let start_addr = process.r2() as *mut u8;
-                            let size = process.r3();
-                            if process.in_exposed_bounds(start_addr, size) {
-                                let slice = ::AppSlice::new(start_addr as *mut u8, size, appid);
-                                d.allow(appid, process.r1(), slice)
-                            } else {
-                                ReturnCode::EINVAL /* memory not allocated to process */
+                let res = if !process.permitted(process.r0(), None) {
+                    ReturnCode::EPERM
+                } else {
+                    platform.with_driver(process.r0(), |driver| {
+                        match driver {
+                            Some(d) => {
+                                let start_addr = process.r2() as *mut u8;
+                                let size = process.r3();
+                                if start_addr.is_null() || size == 0 {
+                                    // A null or empty buffer revokes the current one
+                                    d.allow(appid, process.r1(), None)
+                                } else if process.in_exposed_bounds(start_addr, size) {
+                                    let slice = ::AppSlice::new(start_addr as *mut u8, size, appid);
+                                    d.allow(appid, process.r1(), Some(slice))
+                                } else {
+                                    ReturnCode::EINVAL /* memory not allocated to process */
+                                }
                             }
+                            None => ReturnCode::ENODEVICE,
                         }
-                        None => ReturnCode::ENODEVICE,
-                    }
-                });
+                    })
+                };
                 process.set_return_code(res);
             }
             _ => {}
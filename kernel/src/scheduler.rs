@@ -0,0 +1,148 @@
+//! Scheduling policies for choosing which process runs next.
+//!
+//! `kernel::main` asks the board's `Scheduler` for the next ready process
+//! every time it has serviced interrupts or a process has stopped running,
+//! and runs that process for the timeslice the scheduler gives it.
+
+use core::cell::Cell;
+use process::{Process, State};
+
+/// Timeslice used when a board does not specify one, in microseconds.
+pub const DEFAULT_TIMESLICE_US: u32 = 10000;
+
+pub trait Scheduler {
+    /// Returns the index of the next process to run, or `None` if no process
+    /// is ready.
+    fn next(&self, processes: &[Option<Process<'static>>]) -> Option<usize>;
+
+    /// How long the process at `index` may run before it is preempted, in
+    /// microseconds. `None` lets it run until it yields.
+    fn timeslice(&self, index: usize) -> Option<u32>;
+}
+
+/// Returns the first ready process after `last`, wrapping around.
+fn next_ready_after(processes: &[Option<Process<'static>>], last: usize) -> Option<usize> {
+    let len = processes.len();
+    for offset in 1..len + 1 {
+        let idx = (last + offset) % len;
+        if processes[idx].as_ref().map_or(false, |p| p.ready()) {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+/// Runs ready processes in turn, each for its own timeslice.
+pub struct RoundRobin<'a> {
+    timeslices: &'a [u32],
+    last: Cell<usize>,
+}
+
+impl<'a> RoundRobin<'a> {
+    /// `timeslices[i]` is the timeslice of process `i`. Processes without an
+    /// entry get `DEFAULT_TIMESLICE_US`.
+    pub const fn new(timeslices: &'a [u32]) -> RoundRobin<'a> {
+        RoundRobin {
+            timeslices: timeslices,
+            last: Cell::new(0),
+        }
+    }
+}
+
+impl<'a> Scheduler for RoundRobin<'a> {
+    fn next(&self, processes: &[Option<Process<'static>>]) -> Option<usize> {
+        let next = next_ready_after(processes, self.last.get());
+        next.map(|idx| self.last.set(idx));
+        next
+    }
+
+    fn timeslice(&self, index: usize) -> Option<u32> {
+        Some(*self.timeslices.get(index).unwrap_or(&DEFAULT_TIMESLICE_US))
+    }
+}
+
+/// Always runs the ready process with the highest priority. Lower values
+/// mean higher priority, and processes of equal priority take turns.
+///
+/// A process that becomes ready because of an interrupt preempts a lower
+/// priority one as soon as the interrupt has been serviced.
+pub struct FixedPriority<'a> {
+    priorities: &'a [usize],
+    timeslice_us: u32,
+    last: Cell<usize>,
+}
+
+impl<'a> FixedPriority<'a> {
+    /// `priorities[i]` is the priority of process `i`. Processes without an
+    /// entry get the lowest priority.
+    pub const fn new(priorities: &'a [usize], timeslice_us: u32) -> FixedPriority<'a> {
+        FixedPriority {
+            priorities: priorities,
+            timeslice_us: timeslice_us,
+            last: Cell::new(0),
+        }
+    }
+
+    fn priority(&self, index: usize) -> usize {
+        *self.priorities.get(index).unwrap_or(&usize::max_value())
+    }
+}
+
+impl<'a> Scheduler for FixedPriority<'a> {
+    fn next(&self, processes: &[Option<Process<'static>>]) -> Option<usize> {
+        let len = processes.len();
+        let last = self.last.get();
+        let mut next = None;
+        for offset in 1..len + 1 {
+            let idx = (last + offset) % len;
+            if !processes[idx].as_ref().map_or(false, |p| p.ready()) {
+                continue;
+            }
+            let better = match next {
+                None => true,
+                Some(best) => self.priority(idx) < self.priority(best),
+            };
+            if better {
+                next = Some(idx);
+            }
+        }
+        next.map(|idx| self.last.set(idx));
+        next
+    }
+
+    fn timeslice(&self, _: usize) -> Option<u32> {
+        Some(self.timeslice_us)
+    }
+}
+
+/// Runs each process until it yields. A process interrupted by hardware is
+/// resumed once the interrupt has been serviced.
+pub struct Cooperative {
+    last: Cell<usize>,
+}
+
+impl Cooperative {
+    pub const fn new() -> Cooperative {
+        Cooperative { last: Cell::new(0) }
+    }
+}
+
+impl Scheduler for Cooperative {
+    fn next(&self, processes: &[Option<Process<'static>>]) -> Option<usize> {
+        let last = self.last.get();
+        let running = processes.get(last)
+            .and_then(|p| p.as_ref())
+            .map_or(false, |p| p.current_state() == State::Running);
+        if running {
+            return Some(last);
+        }
+
+        let next = next_ready_after(processes, last);
+        next.map(|idx| self.last.set(idx));
+        next
+    }
+
+    fn timeslice(&self, _: usize) -> Option<u32> {
+        None
+    }
+}
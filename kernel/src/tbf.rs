@@ -0,0 +1,439 @@
+//! Parsing of Tock Binary Format (TBF) app headers.
+//!
+//! Every app image in flash starts with a header describing where its
+//! segments are and how much memory it needs. Three versions are understood:
+//!
+//! Version 1 is a fixed block of 19 little-endian words, the last of which is
+//! the XOR of all the others.
+//!
+//! Version 2 is version 1 with a callback queue length word added before the
+//! checksum, 20 words in all.
+//!
+//! Version 3 is a fixed base followed by type-length-value (TLV) entries:
+//!
+//! ```text
+//!  base:  version: u32        (3)
+//!         header_size: u32    (bytes, including the base and all TLVs)
+//!         total_size: u32     (bytes of the whole padded image)
+//!         checksum: u32       (XOR of every other word of the header)
+//!  TLV:   type: u16
+//!         length: u16         (bytes of value, excluding padding)
+//!         value: [u8]         (padded to a multiple of 4 bytes)
+//! ```
+//!
+//! TLVs of unknown type are skipped, so new entries can be added without
+//! breaking older kernels. A `Main` entry is required, and no type known to
+//! the kernel may appear more than once.
+//!
+//! A version 3 image may carry a SHA-256 hash of itself in a `Sha256` entry,
+//! and an ECDSA P-256 signature of that hash in a `Signature` entry. The hash
+//! covers the whole image (`total_size` bytes), with the checksum word, the
+//! hash value and the signature value taken to be zero.
+
+use common::p256;
+use common::sha256::{self, Sha256};
+use core::cmp;
+use core::ptr::read_volatile;
+use core::slice;
+use process::DEFAULT_CALLBACK_QUEUE_LEN;
+
+/// Size in bytes of the fixed part of a version 3 header.
+const BASE_HEADER_SIZE: usize = 16;
+
+/// Size in bytes of a TLV's type and length fields.
+const TLV_HEADER_SIZE: usize = 4;
+
+/// Number of words in the value of a `Main` TLV.
+const MAIN_WORDS: usize = 14;
+
+/// Largest header `parse_header` accepts, in bytes.
+pub const MAX_BUFFERED_HEADER_SIZE: usize = 512;
+
+/// Types of the TLV entries in a version 3 header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TbfHeaderType {
+    /// Segment offsets and sizes and minimum memory requirements, as 14
+    /// words: entry_offset, rel_data_offset, rel_data_size, text_offset,
+    /// text_size, got_offset, got_size, data_offset, data_size,
+    /// bss_mem_offset, bss_size, min_stack_len, min_app_heap_len and
+    /// min_kernel_heap_len.
+    Main = 1,
+    /// Pairs of (offset, size) words describing regions of the image the app
+    /// may write to.
+    WriteableFlashRegions = 2,
+    /// The package name, as UTF-8 bytes.
+    PackageName = 3,
+    /// Number of callbacks the kernel queues for the app, as one word.
+    CallbackQueueLen = 4,
+    /// Syscall drivers the app may use, as pairs of words: the driver number
+    /// and a bitmask of the command numbers 0 to 31 the app may issue to it,
+    /// where 0 allows every command. This is the only format of the entry;
+    /// a value that is not a whole number of pairs is rejected.
+    Permissions = 5,
+    /// SHA-256 hash of the image, as 32 bytes.
+    Sha256 = 6,
+    /// ECDSA P-256 signature of the SHA-256 hash of the image, as the 32-byte
+    /// big-endian `r` and `s` values.
+    Signature = 7,
+    /// Most bytes of grant memory the kernel allocates for the app, as one
+    /// word.
+    GrantLimit = 8,
+}
+
+/// Header of an app image, in a form independent of the header version.
+///
+/// All offsets are in bytes from the start of the image.
+#[derive(Clone, Copy, Debug)]
+pub struct LoadInfo {
+    pub version: u32,
+    pub header_size: u32,
+    pub total_size: u32,
+    pub entry_offset: u32,
+    pub rel_data_offset: u32,
+    pub rel_data_size: u32,
+    pub text_offset: u32,
+    pub text_size: u32,
+    pub got_offset: u32,
+    pub got_size: u32,
+    pub data_offset: u32,
+    pub data_size: u32,
+    pub bss_mem_offset: u32,
+    pub bss_size: u32,
+    pub min_stack_len: u32,
+    pub min_app_heap_len: u32,
+    pub min_kernel_heap_len: u32,
+    pub pkg_name_offset: u32,
+    pub pkg_name_size: u32,
+    /// Number of callbacks that can be queued for the process. Zero selects
+    /// `DEFAULT_CALLBACK_QUEUE_LEN`.
+    pub callback_queue_len: u32,
+    /// Most bytes of grant memory the kernel allocates for the process, or
+    /// zero for no limit.
+    pub grant_limit: u32,
+    /// Offset of the value of the `Sha256` entry, or zero if there is none.
+    pub sha256_offset: u32,
+    /// Offset of the value of the `Signature` entry, or zero if there is none.
+    pub signature_offset: u32,
+}
+
+impl LoadInfo {
+    pub fn callback_queue_len(&self) -> usize {
+        if self.callback_queue_len == 0 {
+            DEFAULT_CALLBACK_QUEUE_LEN
+        } else {
+            self.callback_queue_len as usize
+        }
+    }
+
+    pub fn grant_limit(&self) -> Option<usize> {
+        if self.grant_limit == 0 {
+            None
+        } else {
+            Some(self.grant_limit as usize)
+        }
+    }
+}
+
+/// Whether the value of a `Permissions` TLV, in the format described at
+/// `TbfHeaderType::Permissions`, lets the app use `driver_num`, and issue
+/// command `command_num` to it if one is given.
+pub fn permits(permissions: &[u8], driver_num: usize, command_num: Option<usize>) -> bool {
+    let word = |bytes: &[u8]| {
+        bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 |
+        (bytes[3] as u32) << 24
+    };
+    for entry in permissions.chunks(8).filter(|entry| entry.len() == 8) {
+        if word(&entry[0..4]) as usize != driver_num {
+            continue;
+        }
+        let commands = word(&entry[4..8]);
+        return match command_num {
+            None => true,
+            Some(_) if commands == 0 => true,
+            Some(num) => num < 32 && commands & (1 << num) != 0,
+        };
+    }
+    false
+}
+
+/// Converts a pointer to memory to a LoadInfo struct
+///
+/// This function takes a pointer to arbitrary memory and Optionally returns a
+/// LoadInfo struct. This function will validate the header checksum, but does
+/// not perform sanity or security checking on the structure
+pub unsafe fn parse_and_validate_load_info(address: *const u8) -> Option<LoadInfo> {
+    match read_volatile(address as *const u32) {
+        1 => parse_fixed(address, 19),
+        2 => parse_fixed(address, 20),
+        3 => parse_tlv(address),
+        _ => None,
+    }
+}
+
+/// Parses and validates a header held in a buffer, such as the first chunk
+/// of an image that is still being received, without reading past the end of
+/// `header`. Headers larger than `MAX_BUFFERED_HEADER_SIZE` are rejected.
+pub fn parse_header(header: &[u8]) -> Option<LoadInfo> {
+    // Copy the header so that it can be read as aligned words
+    let mut words = [0u32; MAX_BUFFERED_HEADER_SIZE / 4];
+    let len = cmp::min(header.len(), MAX_BUFFERED_HEADER_SIZE) / 4;
+    for (i, word) in words[..len].iter_mut().enumerate() {
+        *word = header[4 * i] as u32 | (header[4 * i + 1] as u32) << 8 |
+                (header[4 * i + 2] as u32) << 16 | (header[4 * i + 3] as u32) << 24;
+    }
+
+    let header_size = match words[0] {
+        1 => 19 * 4,
+        2 => 20 * 4,
+        3 => words[1] as usize,
+        _ => return None,
+    };
+    if len < BASE_HEADER_SIZE / 4 || header_size > len * 4 {
+        return None;
+    }
+    unsafe { parse_and_validate_load_info(words.as_ptr() as *const u8) }
+}
+
+/// Parses a version 1 or 2 header of `num_words` words.
+unsafe fn parse_fixed(address: *const u8, num_words: usize) -> Option<LoadInfo> {
+    let words = slice::from_raw_parts(address as *const u32, num_words);
+
+    let checksum = words[..num_words - 1].iter().fold(0, |checksum, word| checksum ^ word);
+    if checksum != words[num_words - 1] {
+        return None;
+    }
+
+    Some(LoadInfo {
+        version: words[0],
+        header_size: (num_words * 4) as u32,
+        total_size: words[1],
+        entry_offset: words[2],
+        rel_data_offset: words[3],
+        rel_data_size: words[4],
+        text_offset: words[5],
+        text_size: words[6],
+        got_offset: words[7],
+        got_size: words[8],
+        data_offset: words[9],
+        data_size: words[10],
+        bss_mem_offset: words[11],
+        bss_size: words[12],
+        min_stack_len: words[13],
+        min_app_heap_len: words[14],
+        min_kernel_heap_len: words[15],
+        pkg_name_offset: words[16],
+        pkg_name_size: words[17],
+        callback_queue_len: if num_words > 19 { words[18] } else { 0 },
+        grant_limit: 0,
+        sha256_offset: 0,
+        signature_offset: 0,
+    })
+}
+
+unsafe fn parse_tlv(address: *const u8) -> Option<LoadInfo> {
+    let base = slice::from_raw_parts(address as *const u32, BASE_HEADER_SIZE / 4);
+    let header_size = base[1] as usize;
+    let total_size = base[2];
+    if header_size < BASE_HEADER_SIZE || header_size % 4 != 0 ||
+       header_size > total_size as usize {
+        return None;
+    }
+
+    let words = slice::from_raw_parts(address as *const u32, header_size / 4);
+    let checksum = words.iter()
+        .enumerate()
+        .filter(|&(i, _)| i != 3)
+        .fold(0, |checksum, (_, word)| checksum ^ word);
+    if checksum != base[3] {
+        return None;
+    }
+
+    let mut main = None;
+    // Types of the entries seen so far, as a bitmask
+    let mut seen = 0u32;
+    let mut load_info = LoadInfo {
+        version: 3,
+        header_size: header_size as u32,
+        total_size: total_size,
+        entry_offset: 0,
+        rel_data_offset: 0,
+        rel_data_size: 0,
+        text_offset: 0,
+        text_size: 0,
+        got_offset: 0,
+        got_size: 0,
+        data_offset: 0,
+        data_size: 0,
+        bss_mem_offset: 0,
+        bss_size: 0,
+        min_stack_len: 0,
+        min_app_heap_len: 0,
+        min_kernel_heap_len: 0,
+        pkg_name_offset: 0,
+        pkg_name_size: 0,
+        callback_queue_len: 0,
+        grant_limit: 0,
+        sha256_offset: 0,
+        signature_offset: 0,
+    };
+
+    let mut offset = BASE_HEADER_SIZE;
+    while offset < header_size {
+        if offset + TLV_HEADER_SIZE > header_size {
+            return None;
+        }
+        let tl = words[offset / 4];
+        let tlv_type = tl & 0xffff;
+        let length = (tl >> 16) as usize;
+        let value_offset = offset + TLV_HEADER_SIZE;
+        let padded_length = (length + 3) & !3;
+        if value_offset + padded_length > header_size {
+            return None;
+        }
+        let value = &words[value_offset / 4..(value_offset + padded_length) / 4];
+
+        // `find_tlv` returns the first entry of a type, so a second one
+        // could differ from what was checked here
+        if tlv_type <= TbfHeaderType::GrantLimit as u32 {
+            if seen & (1 << tlv_type) != 0 {
+                return None;
+            }
+            seen |= 1 << tlv_type;
+        }
+
+        if tlv_type == TbfHeaderType::Main as u32 {
+            if length != MAIN_WORDS * 4 {
+                return None;
+            }
+            main = Some(value);
+        } else if tlv_type == TbfHeaderType::PackageName as u32 {
+            load_info.pkg_name_offset = value_offset as u32;
+            load_info.pkg_name_size = length as u32;
+        } else if tlv_type == TbfHeaderType::Permissions as u32 {
+            if length % 8 != 0 {
+                return None;
+            }
+        } else if tlv_type == TbfHeaderType::CallbackQueueLen as u32 {
+            if length != 4 {
+                return None;
+            }
+            load_info.callback_queue_len = value[0];
+        } else if tlv_type == TbfHeaderType::GrantLimit as u32 {
+            if length != 4 {
+                return None;
+            }
+            load_info.grant_limit = value[0];
+        } else if tlv_type == TbfHeaderType::Sha256 as u32 {
+            if length != sha256::DIGEST_LEN {
+                return None;
+            }
+            load_info.sha256_offset = value_offset as u32;
+        } else if tlv_type == TbfHeaderType::Signature as u32 {
+            if length != p256::SIGNATURE_LEN {
+                return None;
+            }
+            load_info.signature_offset = value_offset as u32;
+        }
+        // Other types are either handled elsewhere or unknown to this kernel
+
+        offset = value_offset + padded_length;
+    }
+
+    main.map(|main| {
+        load_info.entry_offset = main[0];
+        load_info.rel_data_offset = main[1];
+        load_info.rel_data_size = main[2];
+        load_info.text_offset = main[3];
+        load_info.text_size = main[4];
+        load_info.got_offset = main[5];
+        load_info.got_size = main[6];
+        load_info.data_offset = main[7];
+        load_info.data_size = main[8];
+        load_info.bss_mem_offset = main[9];
+        load_info.bss_size = main[10];
+        load_info.min_stack_len = main[11];
+        load_info.min_app_heap_len = main[12];
+        load_info.min_kernel_heap_len = main[13];
+        load_info
+    })
+}
+
+/// Returns the value of the TLV of type `tlv_type` in the version 3 header of
+/// the image at `address`, or `None` if the header is not version 3 or has no
+/// such entry. The header must already have been validated.
+pub unsafe fn find_tlv(address: *const u8, tlv_type: TbfHeaderType) -> Option<&'static [u8]> {
+    let load_info = match parse_and_validate_load_info(address) {
+        Some(load_info) if load_info.version == 3 => load_info,
+        _ => return None,
+    };
+    let header_size = load_info.header_size as usize;
+    let mut offset = BASE_HEADER_SIZE;
+    while offset + TLV_HEADER_SIZE <= header_size {
+        let tl = read_volatile(address.offset(offset as isize) as *const u32);
+        let length = (tl >> 16) as usize;
+        let value_offset = offset + TLV_HEADER_SIZE;
+        if tl & 0xffff == tlv_type as u32 {
+            return Some(slice::from_raw_parts(address.offset(value_offset as isize), length));
+        }
+        offset = value_offset + ((length + 3) & !3);
+    }
+    None
+}
+
+/// Computes the SHA-256 digest of an image, with the header checksum, the
+/// stored hash and the signature replaced by zeros.
+pub unsafe fn image_digest(address: *const u8, load_info: &LoadInfo) -> [u8; sha256::DIGEST_LEN] {
+    let image = slice::from_raw_parts(address, load_info.total_size as usize);
+
+    // Ranges of the image to hash as zeros, in increasing order
+    let mut zeroed = [(0, 0); 3];
+    if load_info.version == 3 {
+        zeroed[0] = (12, 4);
+    }
+    if load_info.sha256_offset != 0 {
+        zeroed[1] = (load_info.sha256_offset as usize, sha256::DIGEST_LEN);
+    }
+    if load_info.signature_offset != 0 {
+        zeroed[2] = (load_info.signature_offset as usize, p256::SIGNATURE_LEN);
+    }
+    if zeroed[1].0 > zeroed[2].0 && zeroed[2].1 > 0 {
+        zeroed.swap(1, 2);
+    }
+
+    let mut hasher = Sha256::new();
+    let mut pos = 0;
+    for &(start, len) in zeroed.iter().filter(|&&(_, len)| len > 0) {
+        hasher.update(&image[pos..start]);
+        for _ in 0..len {
+            hasher.update(&[0]);
+        }
+        pos = start + len;
+    }
+    hasher.update(&image[pos..]);
+    hasher.finish()
+}
+
+/// Checks the image against the hash in its header. Images without a hash
+/// always pass.
+pub unsafe fn verify_sha256(address: *const u8, load_info: &LoadInfo) -> bool {
+    if load_info.sha256_offset == 0 {
+        return true;
+    }
+    let expected = slice::from_raw_parts(address.offset(load_info.sha256_offset as isize),
+                                         sha256::DIGEST_LEN);
+    &image_digest(address, load_info)[..] == expected
+}
+
+/// Checks the signature in the image header against `public_key`. Images
+/// without a signature fail.
+pub unsafe fn verify_signature(address: *const u8,
+                               load_info: &LoadInfo,
+                               public_key: &[u8; p256::PUBLIC_KEY_LEN])
+                               -> bool {
+    if load_info.signature_offset == 0 {
+        return false;
+    }
+    let signature = &*(address.offset(load_info.signature_offset as isize) as
+                       *const [u8; p256::SIGNATURE_LEN]);
+    p256::verify(public_key, &image_digest(address, load_info), signature)
+}
@@ -0,0 +1,44 @@
+#include "app_loader.h"
+
+struct write_result {
+  bool fired;
+  int status;
+};
+
+static void write_cb(int status,
+                     __attribute__ ((unused)) int written,
+                     __attribute__ ((unused)) int unused,
+                     void* ud) {
+  struct write_result* result = (struct write_result*) ud;
+  result->status = status;
+  result->fired = true;
+}
+
+int app_loader_chunk_size(void) {
+  return command(DRIVER_NUM_APP_LOADER, 1, 0);
+}
+
+int app_loader_begin(size_t size) {
+  return command(DRIVER_NUM_APP_LOADER, 2, size);
+}
+
+int app_loader_write_chunk(const uint8_t* chunk, size_t len) {
+  int err;
+  struct write_result result = { .fired = false };
+
+  err = subscribe(DRIVER_NUM_APP_LOADER, 0, write_cb, (void*) &result);
+  if (err < 0) return err;
+
+  err = allow(DRIVER_NUM_APP_LOADER, 0, (void*) chunk, len);
+  if (err < 0) return err;
+
+  err = command(DRIVER_NUM_APP_LOADER, 3, 0);
+  if (err < 0) return err;
+
+  yield_for(&result.fired);
+  return result.status;
+}
+
+int app_loader_finish(void) {
+  return command(DRIVER_NUM_APP_LOADER, 4, 0);
+}
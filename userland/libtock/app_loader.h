@@ -0,0 +1,41 @@
+#pragma once
+
+#include "tock.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DRIVER_NUM_APP_LOADER 17
+
+/*  app_loader_chunk_size
+ *  Only available to the board's installer app.
+ *  returns the size of the chunks images are written in, negative on
+ *  failure.
+ */
+int app_loader_chunk_size(void);
+
+/*  app_loader_begin
+ *  Starts installing an image of `size` bytes, a power of two as produced
+ *  by elf2tbf, into free app flash.
+ *  returns 0 on success, negative on failure.
+ */
+int app_loader_begin(size_t size);
+
+/*  app_loader_write_chunk
+ *  Writes the next chunk of the image and waits for the write to finish.
+ *  The header in the first chunk is validated before it is written.
+ *    chunk: app_loader_chunk_size() bytes of the image.
+ *  returns 0 on success, negative on failure.
+ */
+int app_loader_write_chunk(const uint8_t* chunk, size_t len);
+
+/*  app_loader_finish
+ *  Loads the fully written image into a free process slot and starts it.
+ *  returns the index of the new process on success, negative on failure.
+ */
+int app_loader_finish(void);
+
+#ifdef __cplusplus
+}
+#endif
@@ -0,0 +1,26 @@
+#include "process_control.h"
+
+int process_control_count(void) {
+  return command(DRIVER_NUM_PROCESS_CONTROL, 1, 0);
+}
+
+int process_control_info(int index, process_info_t* info) {
+  int err;
+
+  err = allow(DRIVER_NUM_PROCESS_CONTROL, 0, (void*) info, sizeof(process_info_t));
+  if (err < 0) return err;
+
+  return command(DRIVER_NUM_PROCESS_CONTROL, 2, index);
+}
+
+int process_control_stop(int index) {
+  return command(DRIVER_NUM_PROCESS_CONTROL, 3, index);
+}
+
+int process_control_resume(int index) {
+  return command(DRIVER_NUM_PROCESS_CONTROL, 4, index);
+}
+
+int process_control_restart(int index) {
+  return command(DRIVER_NUM_PROCESS_CONTROL, 5, index);
+}
@@ -0,0 +1,63 @@
+#pragma once
+
+#include "tock.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DRIVER_NUM_PROCESS_CONTROL 16
+
+#define PROCESS_STATE_RUNNING 0
+#define PROCESS_STATE_YIELDED 1
+#define PROCESS_STATE_FAULT   2
+#define PROCESS_STATE_STOPPED 3
+
+#define PROCESS_NAME_MAX_LEN 32
+
+typedef struct {
+  uint32_t state;
+  uint32_t syscall_count;
+  uint32_t memory_size;
+  uint32_t app_memory_used;
+  uint32_t grant_bytes_used;
+  uint32_t name_len;
+  char name[PROCESS_NAME_MAX_LEN];
+} process_info_t;
+
+/*  process_control_count
+ *  Only available to the board's controlling app.
+ *  returns the number of process slots on success, negative on failure.
+ */
+int process_control_count(void);
+
+/*  process_control_info
+ *  Reads the state, memory usage and package name of a process. The name is
+ *  truncated to PROCESS_NAME_MAX_LEN bytes and is not NUL-terminated.
+ *    index: process slot to query.
+ *    info: filled in with information about the process.
+ *  returns 0 on success, negative on failure.
+ */
+int process_control_info(int index, process_info_t* info);
+
+/*  process_control_stop
+ *  Stops a running process until it is resumed or restarted.
+ *  returns 0 on success, negative on failure.
+ */
+int process_control_stop(int index);
+
+/*  process_control_resume
+ *  Lets a stopped process continue from where it was stopped.
+ *  returns 0 on success, negative on failure.
+ */
+int process_control_resume(int index);
+
+/*  process_control_restart
+ *  Reloads a process from flash and starts it from its entry point.
+ *  returns 0 on success, negative on failure.
+ */
+int process_control_restart(int index);
+
+#ifdef __cplusplus
+}
+#endif
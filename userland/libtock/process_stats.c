@@ -0,0 +1,14 @@
+#include "process_stats.h"
+
+int process_stats_count(void) {
+  return command(DRIVER_NUM_PROCESS_STATS, 1, 0);
+}
+
+int process_stats_get(int index, process_stats_t* stats) {
+  int err;
+
+  err = allow(DRIVER_NUM_PROCESS_STATS, 0, (void*) stats, sizeof(process_stats_t));
+  if (err < 0) return err;
+
+  return command(DRIVER_NUM_PROCESS_STATS, 2, index);
+}
@@ -0,0 +1,41 @@
+#pragma once
+
+#include "tock.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DRIVER_NUM_PROCESS_STATS 15
+
+typedef struct {
+  uint32_t state;
+  uint32_t syscall_count;
+  uint32_t cpu_time_us;
+  uint32_t timeslice_expirations;
+  uint32_t callbacks_delivered;
+  uint32_t callbacks_dropped;
+  uint32_t grant_bytes_used;
+  uint32_t restart_count;
+  uint32_t grant_bytes_allocated;
+  uint32_t grant_limit;           // 0 if the app has no grant limit
+  uint32_t grant_alloc_failures;
+} process_stats_t;
+
+/*  process_stats_count
+ *  Only available to the board's monitoring app.
+ *  returns the number of process slots on success, negative on failure.
+ */
+int process_stats_count(void);
+
+/*  process_stats_get
+ *  Reads the accounting information of a process.
+ *    index: process slot to query.
+ *    stats: filled in with the statistics of the process.
+ *  returns 0 on success, negative on failure.
+ */
+int process_stats_get(int index, process_stats_t* stats);
+
+#ifdef __cplusplus
+}
+#endif
@@ -31,5 +31,16 @@ int timer_stop() {
 }
 
 unsigned int timer_read() {
-  return (unsigned int) command(3, 4, 0);
+  uint32_t now = 0, frequency;
+  timer_read_with_frequency(&now, &frequency);
+  return now;
+}
+
+int timer_read_with_frequency(uint32_t* now, uint32_t* frequency) {
+  uint32_t values[3];
+  int ret = command_values(3, 4, 0, values);
+  if (ret < 0) return ret;
+  *now = values[0];
+  *frequency = values[1];
+  return ret;
 }
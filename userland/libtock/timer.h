@@ -39,6 +39,12 @@ int timer_stop();
  */
 unsigned int timer_read();
 
+/*
+ * Get the current counter value of the timer and the frequency, in Hz, it
+ * counts at.
+ */
+int timer_read_with_frequency(uint32_t* now, uint32_t* frequency);
+
 /*
  * Blocks for the given amount of time in millisecond.
  *
@@ -30,6 +30,18 @@ int command(uint32_t driver, uint32_t command, int data) {
   return ret;
 }
 
+int command_values(uint32_t driver, uint32_t command, int data, uint32_t values[3]) {
+  register uint32_t r0 __asm__ ("r0") = driver;
+  register uint32_t r1 __asm__ ("r1") = command;
+  register int r2 __asm__ ("r2") = data;
+  register uint32_t r3 __asm__ ("r3");
+  asm volatile("svc 2" : "+r" (r0), "+r" (r1), "+r" (r2), "=r" (r3) :: "memory");
+  values[0] = r1;
+  values[1] = (uint32_t) r2;
+  values[2] = r3;
+  return (int) r0;
+}
+
 int allow(uint32_t driver, uint32_t allow, void* ptr, size_t size) {
   register int ret __asm__ ("r0");
   asm volatile("svc 3\nbx lr" ::: "memory", "r0");
@@ -14,6 +14,9 @@ typedef void (subscribe_cb)(int, int, int,void*);
 void yield();
 void yield_for(bool*);
 int command(uint32_t driver, uint32_t command, int data);
+// Like command, but also reads the up to three words a driver can return
+// alongside SUCCESS into `values`.
+int command_values(uint32_t driver, uint32_t command, int data, uint32_t values[3]);
 int subscribe(uint32_t driver, uint32_t subscribe,
               subscribe_cb cb, void* userdata);
 int allow(uint32_t driver, uint32_t allow, void* ptr, size_t size);
@@ -38,6 +41,7 @@ bool driver_exists(uint32_t driver);
 #define ENOMEM   -9
 #define ENOSUPPORT -10
 #define ENODEVICE  -11
+#define EPERM      -12
 
 #ifdef __cplusplus
 }
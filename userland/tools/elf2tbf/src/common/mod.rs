@@ -0,0 +1,7 @@
+//! The kernel's SHA-256 and P-256 code, built here too so that images are
+//! hashed and signed exactly as the kernel checks them.
+
+#[path = "../../../../../kernel/src/common/p256.rs"]
+pub mod p256;
+#[path = "../../../../../kernel/src/common/sha256.rs"]
+pub mod sha256;